@@ -0,0 +1,103 @@
+// Times the built-in searches on generated mazes:
+//
+//     cargo bench --bench builtin
+//
+// The mazes come from fixed seeds, so runs are comparable across commits.
+// The number of expanded cells is printed once per scenario before timing.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use pathfind_demo::grid::{Connectivity, Grid};
+use pathfind_demo::pathfind::builtin::{self, Algorithm};
+
+const SIZES: [usize; 4] = [32, 64, 128, 200];
+const SEED: u64 = 1;
+const DENSE_WALL_PROBABILITY: f32 = 0.3;
+
+const ALGORITHMS: [(&str, Algorithm); 3] = [
+    ("bfs", Algorithm::Bfs),
+    ("astar", Algorithm::AStar),
+    ("bidirectional_bfs", Algorithm::BidirectionalBfs),
+];
+
+struct Scenario {
+    name: &'static str,
+    grid: Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+}
+
+fn open_field(size: usize) -> Grid<bool> {
+    Grid::new(size, size)
+}
+
+fn dense_maze(size: usize) -> Grid<bool> {
+    let mut grid = Grid::new(size, size);
+    grid.random_fill(DENSE_WALL_PROBABILITY, SEED);
+    grid
+}
+
+// A full wall down the middle, so every search has to exhaust its side.
+fn no_solution(size: usize) -> Grid<bool> {
+    Grid::from_fn(size, size, |x, _| x == size / 2)
+}
+
+fn scenarios(size: usize) -> Vec<Scenario> {
+    let start = (0, 0);
+    let finish = (size - 1, size - 1);
+    vec![
+        ("open_field", open_field(size)),
+        ("dense_maze", dense_maze(size)),
+        ("no_solution", no_solution(size)),
+    ]
+    .into_iter()
+    .map(|(name, mut grid)| {
+        grid.set(start.0, start.1, false);
+        grid.set(finish.0, finish.1, false);
+        Scenario {
+            name,
+            grid,
+            start,
+            finish,
+        }
+    })
+    .collect()
+}
+
+fn bench_builtin(c: &mut Criterion) {
+    for &size in &SIZES {
+        for scenario in scenarios(size) {
+            let mut group = c.benchmark_group(format!("{}/{}x{}", scenario.name, size, size));
+            for &(name, algorithm) in &ALGORITHMS {
+                let stats = builtin::find_path(
+                    algorithm,
+                    &scenario.grid,
+                    scenario.start,
+                    scenario.finish,
+                    Connectivity::Four,
+                )
+                .stats();
+                println!(
+                    "{}/{}x{}/{}: expanded {}, path steps {:?}",
+                    scenario.name, size, size, name, stats.expanded, stats.path_steps
+                );
+
+                group.bench_with_input(BenchmarkId::from_parameter(name), &algorithm, |b, &algorithm| {
+                    b.iter(|| {
+                        builtin::find_path(
+                            algorithm,
+                            &scenario.grid,
+                            scenario.start,
+                            scenario.finish,
+                            Connectivity::Four,
+                        )
+                    })
+                });
+            }
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, bench_builtin);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+// Compares the two ways user code can get at the grid on a 200x200 maze:
+//
+//     cargo bench --bench python_grid
+//
+// `nested_list` converts the whole grid into Python lists before the call,
+// `proxy` hands over the `GridProxy` whose lookups go to the Rust grid. The
+// `pass` scripts return right away, so they time only the conversion; the
+// `scan` scripts look at every cell once.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use pathfind_demo::grid::Grid;
+use pathfind_demo::pathfind::{find_path, SearchOptions};
+
+const SIZE: usize = 200;
+const SEED: u64 = 1;
+const WALL_PROBABILITY: f32 = 0.3;
+
+const PASS: &str = "
+def find_path(grid, start, finish):
+    return None
+";
+
+// `{}` is replaced with the wall lookup of cell (row, col).
+const SCAN: &str = "
+def find_path(grid, start, finish):
+    walls = 0
+    for row in range(HEIGHT):
+        for col in range(WIDTH):
+            if {}:
+                walls += 1
+    return None
+";
+
+const MODES: [(&str, bool, &str); 2] = [
+    ("nested_list", true, "grid[row][col]"),
+    ("proxy", false, "grid.is_wall(row, col)"),
+];
+
+fn bench_python_grid(c: &mut Criterion) {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.random_fill(WALL_PROBABILITY, SEED);
+    let (start, finish) = ((0, 0), (SIZE - 1, SIZE - 1));
+    grid.set(start.0, start.1, false);
+    grid.set(finish.0, finish.1, false);
+
+    for &script_name in &["pass", "scan"] {
+        let mut group = c.benchmark_group(format!("python_grid/{}x{}/{}", SIZE, SIZE, script_name));
+        group.sample_size(10);
+        for &(name, grid_as_list, lookup) in &MODES {
+            let script = match script_name {
+                "pass" => PASS.to_owned(),
+                _ => SCAN.replace("{}", lookup),
+            };
+            let options = SearchOptions {
+                grid_as_list,
+                ..SearchOptions::default()
+            };
+            group.bench_with_input(BenchmarkId::from_parameter(name), &options, |b, options| {
+                b.iter(|| find_path(&script, &grid, start, finish, options).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_python_grid);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+// Compares building wall geometry one rectangle at a time, the way each
+// `draw_rectangle` call did before walls were batched, with the batches
+// `MacroquadDrawContext::draw_rectangles` submits now:
+//
+//     cargo bench --bench walls
+//
+// Only the CPU side runs here, since submitting to quad-gl needs a window.
+// The number of geometry submissions per frame is printed for both ways
+// before timing, that count is what the batching saves on the GPU side.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use pathfind_demo::grid::Grid;
+use pathfind_demo::runner::{colors, push_rect_geometry, Rect, MAX_RECTS_PER_BATCH};
+use quad_gl::Vertex;
+
+const SIZES: [usize; 3] = [64, 128, 200];
+const SEED: u64 = 1;
+const WALL_PROBABILITY: f32 = 0.3;
+
+fn wall_rects(size: usize) -> Vec<Rect> {
+    let mut grid = Grid::new(size, size);
+    grid.random_fill(WALL_PROBABILITY, SEED);
+    grid.iter()
+        .filter(|&(_, _, is_wall)| is_wall)
+        .map(|(x, y, _)| Rect::new(x as f32, y as f32, 1., 1.))
+        .collect()
+}
+
+fn per_rect(rects: &[Rect]) {
+    let mut vertices = Vec::with_capacity(4);
+    let mut indices = Vec::with_capacity(6);
+    for rect in rects {
+        vertices.clear();
+        indices.clear();
+        push_rect_geometry(std::slice::from_ref(rect), colors::BLACK, &mut vertices, &mut indices);
+        black_box((&vertices, &indices));
+    }
+}
+
+fn batched(rects: &[Rect]) {
+    let mut vertices: Vec<Vertex> = Vec::with_capacity(4 * MAX_RECTS_PER_BATCH);
+    let mut indices = Vec::with_capacity(6 * MAX_RECTS_PER_BATCH);
+    for batch in rects.chunks(MAX_RECTS_PER_BATCH) {
+        vertices.clear();
+        indices.clear();
+        push_rect_geometry(batch, colors::BLACK, &mut vertices, &mut indices);
+        black_box((&vertices, &indices));
+    }
+}
+
+fn bench_walls(c: &mut Criterion) {
+    for &size in &SIZES {
+        let rects = wall_rects(size);
+        println!(
+            "walls/{}x{}: {} walls, {} submissions one by one, {} batched",
+            size,
+            size,
+            rects.len(),
+            rects.len(),
+            (rects.len() + MAX_RECTS_PER_BATCH - 1) / MAX_RECTS_PER_BATCH
+        );
+
+        let mut group = c.benchmark_group(format!("walls/{}x{}", size, size));
+        group.bench_with_input(BenchmarkId::from_parameter("per_rect"), &rects, |b, rects| {
+            b.iter(|| per_rect(rects))
+        });
+        group.bench_with_input(BenchmarkId::from_parameter("batched"), &rects, |b, rects| {
+            b.iter(|| batched(rects))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_walls);
+criterion_main!(benches);
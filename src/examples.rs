@@ -0,0 +1,132 @@
+pub struct Example {
+    pub name: &'static str,
+    pub code: &'static str,
+}
+
+pub const EXAMPLES: [Example; 4] = [
+    Example {
+        name: "BFS",
+        code: include_str!("python/examples/bfs.py"),
+    },
+    Example {
+        name: "DFS",
+        code: include_str!("python/examples/dfs.py"),
+    },
+    Example {
+        name: "A*",
+        code: include_str!("python/examples/astar.py"),
+    },
+    Example {
+        name: "Greedy",
+        code: include_str!("python/examples/greedy.py"),
+    },
+];
+
+// The code last loaded into each editor slot, an example at first. Replacing
+// a slot's code loses work whenever the editor holds anything else, even code
+// that was already run, because the slot autosaves and keeps no other copy.
+pub struct LoadedCode {
+    slots: Vec<String>,
+}
+
+impl LoadedCode {
+    pub fn new() -> Self {
+        Self {
+            slots: EXAMPLES.iter().map(|example| example.code.to_owned()).collect(),
+        }
+    }
+
+    pub fn load(&mut self, slot: usize, code: &str) {
+        self.slots[slot] = code.to_owned();
+    }
+
+    // Edits that left the editor empty or identical to one of the examples
+    // lose nothing.
+    pub fn would_lose_edits(&self, slot: usize, code: &str) -> bool {
+        !code.trim().is_empty()
+            && code != self.slots[slot]
+            && !EXAMPLES.iter().any(|example| example.code == code)
+    }
+}
+
+impl Default for LoadedCode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::pathfind::{find_and_render_path, SearchOptions};
+
+    // `find_and_render_path` rejects paths that don't lead from the start to
+    // the finish through open, adjacent cells.
+    fn path_steps(example: &Example, grid: &Grid<bool>) -> usize {
+        let (start, finish) = ((0, 0), (grid.width() - 1, grid.height() - 1));
+        let search = find_and_render_path(example.code, grid, start, finish, &SearchOptions::default())
+            .unwrap_or_else(|err| panic!("{} failed: {}", example.name, err));
+        let path = search
+            .path
+            .unwrap_or_else(|| panic!("{} found no path", example.name));
+        path.len() - 1
+    }
+
+    // Every odd column is a wall with a gap at alternating ends, so the only
+    // way through winds up and down 28 steps.
+    fn winding_maze() -> Grid<bool> {
+        Grid::from_fn(9, 5, |x, y| x % 2 == 1 && y != if x % 4 == 1 { 4 } else { 0 })
+    }
+
+    #[test]
+    fn examples_find_valid_paths_on_the_default_grid() {
+        for example in &EXAMPLES {
+            assert!(path_steps(example, &Grid::new(20, 20)) >= 38, "{}", example.name);
+        }
+    }
+
+    #[test]
+    fn examples_find_valid_paths_around_walls() {
+        let grid = winding_maze();
+        for example in &EXAMPLES {
+            assert_eq!(path_steps(example, &grid), 28, "{}", example.name);
+        }
+    }
+
+    #[test]
+    fn bfs_and_astar_examples_find_shortest_paths_in_the_open() {
+        for example in EXAMPLES.iter().filter(|example| example.name == "BFS" || example.name == "A*") {
+            assert_eq!(path_steps(example, &Grid::new(20, 20)), 38, "{}", example.name);
+        }
+    }
+
+    #[test]
+    fn running_edited_code_still_protects_it() {
+        let mut loaded = LoadedCode::new();
+        let edited = format!("{}\n# my tweak\n", EXAMPLES[0].code);
+        // Typing and then running the code doesn't touch what was loaded.
+        assert!(loaded.would_lose_edits(0, &edited));
+
+        loaded.load(0, EXAMPLES[1].code);
+        assert!(!loaded.would_lose_edits(0, EXAMPLES[1].code));
+    }
+
+    #[test]
+    fn code_loaded_from_outside_can_be_replaced_until_edited() {
+        let mut loaded = LoadedCode::new();
+        let shared = "def find_path(grid, start, finish):\n    return None\n";
+        loaded.load(2, shared);
+        assert!(!loaded.would_lose_edits(2, shared));
+        assert!(loaded.would_lose_edits(2, &format!("{}# edited\n", shared)));
+        // Other slots keep their own baseline.
+        assert!(loaded.would_lose_edits(1, shared));
+    }
+
+    #[test]
+    fn empty_code_and_unchanged_examples_lose_nothing() {
+        let loaded = LoadedCode::new();
+        assert!(!loaded.would_lose_edits(0, "  \n"));
+        assert!(!loaded.would_lose_edits(0, EXAMPLES[3].code));
+    }
+}
@@ -3,7 +3,182 @@ use rustpython_vm::{
     VirtualMachine,
 };
 
-#[derive(Clone)]
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cell ({}, {}) is outside of the {}x{} grid",
+            self.x, self.y, self.width, self.height
+        )
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FromBytesError {
+    MissingHeader,
+    Empty,
+    LengthMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for FromBytesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FromBytesError::MissingHeader => write!(f, "grid bytes are shorter than the header"),
+            FromBytesError::Empty => write!(f, "grid has no cells"),
+            FromBytesError::LengthMismatch { expected, found } => write!(
+                f,
+                "expected {} bytes of cells, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FromBytesError {}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    Four,
+    Eight,
+    // Diagonal moves too, except squeezing between two walls that touch at a
+    // corner, see `Grid::is_corner_cut`.
+    EightNoCornerCutting,
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Connectivity::Four
+    }
+}
+
+impl Connectivity {
+    pub fn deltas(self) -> &'static [(i64, i64)] {
+        const ORTHOGONAL: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+        const ALL: [(i64, i64); 8] = [
+            (1, 0),
+            (0, 1),
+            (-1, 0),
+            (0, -1),
+            (1, 1),
+            (-1, 1),
+            (-1, -1),
+            (1, -1),
+        ];
+        match self {
+            Connectivity::Four => &ORTHOGONAL,
+            Connectivity::Eight | Connectivity::EightNoCornerCutting => &ALL,
+        }
+    }
+
+    pub fn are_adjacent(self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let delta = (b.0 as i64 - a.0 as i64, b.1 as i64 - a.1 as i64);
+        self.deltas().contains(&delta)
+    }
+
+    pub fn allows_corner_cutting(self) -> bool {
+        !matches!(self, Connectivity::EightNoCornerCutting)
+    }
+
+    // Switches between orthogonal and diagonal moves, dropping the corner rule.
+    pub fn toggled(self) -> Self {
+        match self {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight | Connectivity::EightNoCornerCutting => Connectivity::Four,
+        }
+    }
+
+    // Four, then Eight, then Eight without corner cutting, then Four again.
+    pub fn next(self) -> Self {
+        match self {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight => Connectivity::EightNoCornerCutting,
+            Connectivity::EightNoCornerCutting => Connectivity::Four,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// How cells are written on the Python side. With `RowCol` a cell is
+// `(row, col)`, i.e. `(y, x)`, and the nested grid list is `grid[row][col]`.
+// With `XY` a cell is `(x, y)` and the list is `grid[x][y]`, which is what
+// scripts got before the convention became configurable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CoordConvention {
+    XY,
+    RowCol,
+}
+
+impl Default for CoordConvention {
+    fn default() -> Self {
+        CoordConvention::RowCol
+    }
+}
+
+impl CoordConvention {
+    // Converts `(x, y)` to the convention's order and back, the mapping is its
+    // own inverse.
+    pub fn apply<T>(self, (a, b): (T, T)) -> (T, T) {
+        match self {
+            CoordConvention::XY => (a, b),
+            CoordConvention::RowCol => (b, a),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Rearrangements of a whole grid, see `Grid::transformed`. `y` points up, so
+// `Rotate90` turns the grid counterclockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GridTransform {
+    Transpose,
+    Rotate90,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl GridTransform {
+    // Size of the grid after the transform.
+    pub fn apply_to_size(self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            GridTransform::Transpose | GridTransform::Rotate90 => (height, width),
+            GridTransform::FlipHorizontal | GridTransform::FlipVertical => (width, height),
+        }
+    }
+
+    // Where the cell ends up in a grid of the given size after the transform.
+    pub fn apply_to_cell(self, (x, y): (usize, usize), (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            GridTransform::Transpose => (y, x),
+            GridTransform::Rotate90 => (height - 1 - y, x),
+            GridTransform::FlipHorizontal => (width - 1 - x, y),
+            GridTransform::FlipVertical => (x, height - 1 - y),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Grid<T> {
     width: usize,
     height: usize,
@@ -19,6 +194,67 @@ impl<T: Default + Copy> Grid<T> {
         }
     }
 
+    /// Builds a grid with `f(x, y)` in every cell, e.g. a checkerboard:
+    ///
+    /// ```
+    /// use pathfind_demo::grid::Grid;
+    ///
+    /// let board = Grid::from_fn(8, 8, |x, y| (x + y) % 2 == 0);
+    /// assert!(board.get(0, 0) && board.get(1, 1));
+    /// assert!(!board.get(1, 0) && !board.get(0, 7));
+    /// ```
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> T) -> Self {
+        let values = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    // Converts every cell, keeping the dimensions.
+    pub fn map<U>(&self, f: impl Fn(T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            values: self.values.iter().map(|&value| f(value)).collect(),
+        }
+    }
+
+    pub fn transformed(&self, transform: GridTransform) -> Self {
+        let size = (self.width, self.height);
+        let (width, height) = transform.apply_to_size(size);
+        let mut result = Self::new(width, height);
+        for (x, y, value) in self.iter() {
+            let (nx, ny) = transform.apply_to_cell((x, y), size);
+            result.set(nx, ny, value);
+        }
+        result
+    }
+
+    // Swaps the axes, cell (x, y) moves to (y, x).
+    pub fn transpose(&self) -> Self {
+        self.transformed(GridTransform::Transpose)
+    }
+
+    // A quarter turn counterclockwise, cell (x, y) moves to (height - 1 - y, x).
+    pub fn rotate90(&self) -> Self {
+        self.transformed(GridTransform::Rotate90)
+    }
+
+    // Mirrors left to right, cell (x, y) moves to (width - 1 - x, y).
+    pub fn flip_horizontal(&self) -> Self {
+        self.transformed(GridTransform::FlipHorizontal)
+    }
+
+    // Mirrors top to bottom, cell (x, y) moves to (x, height - 1 - y).
+    pub fn flip_vertical(&self) -> Self {
+        self.transformed(GridTransform::FlipVertical)
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -27,6 +263,18 @@ impl<T: Default + Copy> Grid<T> {
         self.height
     }
 
+    pub fn resize(&mut self, new_width: usize, new_height: usize, fill: T) {
+        let mut values = vec![fill; new_width * new_height];
+        for y in 0..self.height.min(new_height) {
+            for x in 0..self.width.min(new_width) {
+                values[y * new_width + x] = self.get(x, y);
+            }
+        }
+        self.width = new_width;
+        self.height = new_height;
+        self.values = values;
+    }
+
     pub fn set(&mut self, x: usize, y: usize, value: T) {
         self.values[y * self.width + x] = value;
     }
@@ -35,6 +283,30 @@ impl<T: Default + Copy> Grid<T> {
         self.values[y * self.width + x]
     }
 
+    pub fn set_checked(&mut self, x: usize, y: usize, value: T) -> Result<(), OutOfBounds> {
+        self.check_bounds(x, y)?;
+        self.set(x, y, value);
+        Ok(())
+    }
+
+    pub fn get_checked(&self, x: usize, y: usize) -> Result<T, OutOfBounds> {
+        self.check_bounds(x, y)?;
+        Ok(self.get(x, y))
+    }
+
+    fn check_bounds(&self, x: usize, y: usize) -> Result<(), OutOfBounds> {
+        if x < self.width && y < self.height {
+            Ok(())
+        } else {
+            Err(OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            })
+        }
+    }
+
     pub fn try_get<U>(&self, x: U, y: U) -> Option<T>
     where
         i64: From<U>,
@@ -56,31 +328,382 @@ impl<T: Default + Copy> Grid<T> {
         x >= 0 && (x as usize) < self.width && y >= 0 && (y as usize) < self.height
     }
 
+    // Column-major like `keys`: all of column 0 bottom to top, then column 1.
     pub fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, usize, T)> + 'a {
         self.keys().map(move |(x, y)| (x, y, self.get(x, y)))
     }
 
+    // Row-major like `keys_rowmajor`: all of row 0 left to right, then row 1.
+    pub fn iter_rowmajor<'a>(&'a self) -> impl Iterator<Item = (usize, usize, T)> + 'a {
+        self.keys_rowmajor().map(move |(x, y)| (x, y, self.get(x, y)))
+    }
+
+    // Unlike `iter`, this walks the cells in storage (row-major) order.
+    pub fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = (usize, usize, &'a mut T)> + 'a {
+        let width = self.width;
+        self.values
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, value)| (i % width, i / width, value))
+    }
+
+    // `x` is the outer loop, so a 2x3 grid yields (0, 0), (0, 1), (0, 2),
+    // (1, 0), (1, 1), (1, 2).
     pub fn keys<'a>(&'a self) -> impl Iterator<Item = (usize, usize)> + 'a {
         (0..self.width)
             .map(move |x| (0..self.height).map(move |y| (x, y)))
             .flatten()
     }
 
+    // `y` is the outer loop, the order of `iter_mut` and `to_bytes`: a 2x3
+    // grid yields (0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2).
+    pub fn keys_rowmajor<'a>(&'a self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
     pub fn neighbors<'a>(
         &'a self,
         x: usize,
         y: usize,
     ) -> impl Iterator<Item = (usize, usize, T)> + 'a {
-        const DELTAS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+        self.neighbors_with(x, y, Connectivity::Four)
+    }
 
-        DELTAS.iter().filter_map(move |(dx, dy)| {
+    pub fn neighbors_with<'a>(
+        &'a self,
+        x: usize,
+        y: usize,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (usize, usize, T)> + 'a {
+        connectivity.deltas().iter().filter_map(move |(dx, dy)| {
             let (nx, ny) = (x as i64 + dx, y as i64 + dy);
             self.try_get(nx, ny)
                 .map(|value| (nx as usize, ny as usize, value))
         })
     }
+
+    // Cells on the Bresenham line from `a` to `b`, both ends included. Points
+    // of the line outside of the grid are skipped.
+    pub fn line<'a>(
+        &'a self,
+        a: (usize, usize),
+        b: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        BresenhamLine::new(a, b).filter(move |&(x, y)| self.are_coordinates_valid(x as i64, y as i64))
+    }
+}
+
+struct BresenhamLine {
+    x: i64,
+    y: i64,
+    end: (i64, i64),
+    dx: i64,
+    dy: i64,
+    sx: i64,
+    sy: i64,
+    err: i64,
+    done: bool,
+}
+
+impl BresenhamLine {
+    fn new(from: (usize, usize), to: (usize, usize)) -> Self {
+        let (x, y) = (from.0 as i64, from.1 as i64);
+        let end = (to.0 as i64, to.1 as i64);
+        let dx = (end.0 - x).abs();
+        let dy = -(end.1 - y).abs();
+        Self {
+            x,
+            y,
+            end,
+            dx,
+            dy,
+            sx: if x < end.0 { 1 } else { -1 },
+            sy: if y < end.1 { 1 } else { -1 },
+            err: dx + dy,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for BresenhamLine {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let cell = (self.x as usize, self.y as usize);
+        if (self.x, self.y) == self.end {
+            self.done = true;
+            return Some(cell);
+        }
+        let e2 = 2 * self.err;
+        if e2 >= self.dy {
+            self.err += self.dy;
+            self.x += self.sx;
+        }
+        if e2 <= self.dx {
+            self.err += self.dx;
+            self.y += self.sy;
+        }
+        Some(cell)
+    }
+}
+
+// A tiny seedable generator so that random fills are reproducible without
+// pulling in a dependency.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl<T> Grid<T> {
+    // Panics with a message naming the offending coordinate rather than the
+    // flat index. `x` has to be checked on its own anyway, or it would wrap
+    // around into the next row.
+    fn checked_index(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.width, "x = {} is outside of grid width {}", x, self.width);
+        assert!(y < self.height, "y = {} is outside of grid height {}", y, self.height);
+        y * self.width + x
+    }
+}
+
+/// `grid[(x, y)]` panics on coordinates outside of the grid; use `try_get` or
+/// the checked variants otherwise.
+///
+/// ```
+/// use pathfind_demo::grid::Grid;
+///
+/// let mut grid = Grid::<bool>::new(3, 2);
+/// grid[(2, 1)] = true;
+/// assert!(grid[(2, 1)] && !grid[(1, 1)]);
+/// ```
+///
+/// ```should_panic
+/// use pathfind_demo::grid::Grid;
+///
+/// let grid = Grid::<bool>::new(3, 2);
+/// grid[(0, 2)]; // y = 2 is outside of grid height 2
+/// ```
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        &self.values[self.checked_index(x, y)]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        let index = self.checked_index(x, y);
+        &mut self.values[index]
+    }
+}
+
+impl Grid<bool> {
+    // Distances between two cells in cell steps, whatever the grid holds.
+    pub fn manhattan(a: (usize, usize), b: (usize, usize)) -> usize {
+        let dx = (a.0 as i64 - b.0 as i64).abs() as usize;
+        let dy = (a.1 as i64 - b.1 as i64).abs() as usize;
+        dx + dy
+    }
+
+    pub fn euclidean(a: (usize, usize), b: (usize, usize)) -> f32 {
+        let dx = a.0 as f32 - b.0 as f32;
+        let dy = a.1 as f32 - b.1 as f32;
+        dx.hypot(dy)
+    }
+
+    pub fn count_true(&self) -> usize {
+        self.values.iter().filter(|&&value| value).count()
+    }
+
+    // Makes every cell a wall with the given probability, deterministically for
+    // a given seed.
+    pub fn random_fill(&mut self, probability: f32, seed: u64) {
+        let probability = probability.max(0.).min(1.) as f64;
+        let mut state = seed;
+        for value in self.values.iter_mut() {
+            let sample = (splitmix64(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+            *value = sample < probability;
+        }
+    }
+
+    // Fraction of cells that are walls, zero for an empty grid.
+    pub fn density(&self) -> f32 {
+        if self.values.is_empty() {
+            0.
+        } else {
+            self.count_true() as f32 / self.values.len() as f32
+        }
+    }
+
+    // A diagonal step between `from` and `to` where both cells it passes by
+    // are walls, so the move slips through the point where they touch.
+    // Orthogonal steps never cut corners.
+    pub fn is_corner_cut(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        from.0 != to.0 && from.1 != to.1 && self.get(to.0, from.1) && self.get(from.0, to.1)
+    }
+
+    // Open cells one legal move away from `(x, y)`.
+    pub fn moves<'a>(
+        &'a self,
+        x: usize,
+        y: usize,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.neighbors_with(x, y, connectivity)
+            .filter(move |&(nx, ny, is_wall)| {
+                !is_wall && (connectivity.allows_corner_cutting() || !self.is_corner_cut((x, y), (nx, ny)))
+            })
+            .map(|(nx, ny, _)| (nx, ny))
+    }
+
+    // Flood fills the open cells reachable from `start`, treating `true` as a
+    // wall. The start cell itself is always included.
+    pub fn reachable_from(&self, start: (usize, usize)) -> HashSet<(usize, usize)> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(start);
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            for (nx, ny, is_wall) in self.neighbors(x, y) {
+                if !is_wall && visited.insert((nx, ny)) {
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        visited
+    }
+
+    // Labels every open cell with the index of its connected component.
+    // Returns the labels together with the number of components.
+    pub fn components(&self) -> (Grid<Option<usize>>, usize) {
+        let mut labels = Grid::<Option<usize>>::new(self.width, self.height);
+        let mut count = 0;
+        for (x, y, is_wall) in self.iter() {
+            if is_wall || labels.get(x, y).is_some() {
+                continue;
+            }
+            for (cx, cy) in self.reachable_from((x, y)) {
+                labels.set(cx, cy, Some(count));
+            }
+            count += 1;
+        }
+        (labels, count)
+    }
+}
+
+// Images larger than this in either dimension are scaled down before being
+// turned into a grid.
+#[cfg(not(target_arch = "wasm32"))]
+pub const MAX_IMAGE_GRID_SIZE: u32 = 100;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Grid<bool> {
+    // Dark pixels become walls and light ones open cells. The top row of the
+    // image is the top of the grid.
+    pub fn from_image<P: AsRef<std::path::Path>>(path: P) -> image::ImageResult<Self> {
+        Ok(Self::from_luma_image(&image::open(path)?.to_luma()))
+    }
+
+    pub fn from_luma_image(image: &image::GrayImage) -> Self {
+        let (width, height) = image.dimensions();
+        let scale = (MAX_IMAGE_GRID_SIZE as f32 / width.max(height) as f32).min(1.);
+        let grid_width = ((width as f32 * scale).round() as u32).max(1);
+        let grid_height = ((height as f32 * scale).round() as u32).max(1);
+        let image = if (grid_width, grid_height) == (width, height) {
+            image.clone()
+        } else {
+            image::imageops::resize(image, grid_width, grid_height, image::FilterType::Triangle)
+        };
+
+        Self::from_fn(grid_width as usize, grid_height as usize, |x, y| {
+            image.get_pixel(x as u32, grid_height - 1 - y as u32)[0] < 128
+        })
+    }
 }
 
+const BYTES_HEADER_SIZE: usize = 8;
+
+// Binary layout, for a compact alternative to the text format in `maze`:
+//
+//     bytes 0..4   width, u32 little endian
+//     bytes 4..8   height, u32 little endian
+//     bytes 8..    one bit per cell, 1 for a wall, in storage order (cell
+//                  (x, y) is bit number y * width + x), least significant
+//                  bit of each byte first, the last byte padded with zeros
+impl Grid<bool> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BYTES_HEADER_SIZE + (self.values.len() + 7) / 8);
+        bytes.extend_from_slice(&(self.width as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.height as u32).to_le_bytes());
+        for chunk in self.values.chunks(8) {
+            let byte = chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, &is_wall)| byte | ((is_wall as u8) << i));
+            bytes.push(byte);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FromBytesError> {
+        if bytes.len() < BYTES_HEADER_SIZE {
+            return Err(FromBytesError::MissingHeader);
+        }
+        let read_u32 = |offset: usize| {
+            let mut word = [0; 4];
+            word.copy_from_slice(&bytes[offset..offset + 4]);
+            u32::from_le_bytes(word) as usize
+        };
+        let (width, height) = (read_u32(0), read_u32(4));
+        if width == 0 || height == 0 {
+            return Err(FromBytesError::Empty);
+        }
+
+        let cells = &bytes[BYTES_HEADER_SIZE..];
+        let expected = width
+            .checked_mul(height)
+            .map(|count| (count + 7) / 8)
+            .unwrap_or(usize::MAX);
+        if cells.len() != expected {
+            return Err(FromBytesError::LengthMismatch {
+                expected,
+                found: cells.len(),
+            });
+        }
+        Ok(Self::from_fn(width, height, |x, y| {
+            let bit = y * width + x;
+            cells[bit / 8] & (1 << (bit % 8)) != 0
+        }))
+    }
+}
+
+impl<T: IntoPyObject + Default + Copy> Grid<T> {
+    pub fn into_pyobject_with(self, convention: CoordConvention, vm: &VirtualMachine) -> PyObjectRef {
+        match convention {
+            CoordConvention::XY => self.into_pyobject(vm),
+            CoordConvention::RowCol => vm.ctx.new_list(
+                (0..self.height)
+                    .map(|y| {
+                        vm.ctx.new_list(
+                            (0..self.width)
+                                .map(|x| self.get(x, y).into_pyobject(vm))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Column-major, `grid[x][y]`; see `into_pyobject_with` for the alternative.
 impl<T: IntoPyObject + Default + Copy> IntoPyObject for Grid<T> {
     fn into_pyobject(self, vm: &VirtualMachine) -> PyObjectRef {
         vm.ctx.new_list(
@@ -96,3 +719,490 @@ impl<T: IntoPyObject + Default + Copy> IntoPyObject for Grid<T> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    // Grids of up to 20x20 with random walls, including sizes whose cell
+    // count isn't a multiple of 8.
+    fn arb_grid() -> impl Strategy<Value = Grid<bool>> {
+        (1usize..20, 1usize..20).prop_flat_map(|(width, height)| {
+            proptest::collection::vec(any::<bool>(), width * height)
+                .prop_map(move |cells| Grid::from_fn(width, height, |x, y| cells[y * width + x]))
+        })
+    }
+
+    fn repeated(grid: &Grid<bool>, transform: GridTransform, times: usize) -> Grid<bool> {
+        (0..times).fold(grid.clone(), |grid, _| grid.transformed(transform))
+    }
+
+    proptest! {
+        #[test]
+        fn bytes_round_trip(grid in arb_grid()) {
+            prop_assert_eq!(Grid::from_bytes(&grid.to_bytes()), Ok(grid.clone()));
+        }
+
+        #[test]
+        fn bytes_have_one_bit_per_cell(grid in arb_grid()) {
+            let cells = grid.width() * grid.height();
+            prop_assert_eq!(grid.to_bytes().len(), BYTES_HEADER_SIZE + (cells + 7) / 8);
+        }
+
+        #[test]
+        fn four_quarter_turns_are_identity(grid in arb_grid()) {
+            prop_assert_eq!(repeated(&grid, GridTransform::Rotate90, 4), grid);
+        }
+
+        #[test]
+        fn transpose_and_flips_are_involutions(grid in arb_grid()) {
+            for &transform in &[
+                GridTransform::Transpose,
+                GridTransform::FlipHorizontal,
+                GridTransform::FlipVertical,
+            ] {
+                prop_assert_eq!(&repeated(&grid, transform, 2), &grid);
+            }
+        }
+
+        #[test]
+        fn half_turn_is_both_flips(grid in arb_grid()) {
+            prop_assert_eq!(grid.rotate90().rotate90(), grid.flip_horizontal().flip_vertical());
+        }
+
+        #[test]
+        fn transforms_keep_the_walls(grid in arb_grid()) {
+            for &transform in &[
+                GridTransform::Transpose,
+                GridTransform::Rotate90,
+                GridTransform::FlipHorizontal,
+                GridTransform::FlipVertical,
+            ] {
+                let transformed = grid.transformed(transform);
+                prop_assert_eq!(
+                    (transformed.width(), transformed.height()),
+                    transform.apply_to_size((grid.width(), grid.height()))
+                );
+                prop_assert_eq!(transformed.count_true(), grid.count_true());
+            }
+        }
+    }
+
+    #[test]
+    fn index_reads_and_writes_cells() {
+        let mut grid = Grid::<usize>::new(3, 2);
+        grid[(2, 1)] = 7;
+        assert_eq!(grid[(2, 1)], 7);
+        assert_eq!(grid.get(2, 1), 7);
+        assert_eq!(grid[(1, 1)], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "x = 3 is outside of grid width 3")]
+    fn index_panics_on_x_outside_of_grid() {
+        let grid = Grid::<bool>::new(3, 2);
+        let _ = grid[(3, 0)];
+    }
+
+    #[test]
+    #[should_panic(expected = "y = 2 is outside of grid height 2")]
+    fn index_panics_on_y_outside_of_grid() {
+        let grid = Grid::<bool>::new(3, 2);
+        let _ = grid[(0, 2)];
+    }
+
+    #[test]
+    #[should_panic(expected = "y = 2 is outside of grid height 2")]
+    fn index_mut_panics_on_y_outside_of_grid() {
+        let mut grid = Grid::<bool>::new(3, 2);
+        grid[(0, 2)] = true;
+    }
+
+    #[test]
+    fn checked_accessors_accept_every_corner() {
+        let mut grid = Grid::<usize>::new(4, 3);
+        for &(x, y) in &[(0, 0), (3, 0), (0, 2), (3, 2)] {
+            assert_eq!(grid.set_checked(x, y, x + y), Ok(()));
+            assert_eq!(grid.get_checked(x, y), Ok(x + y));
+        }
+    }
+
+    #[test]
+    fn checked_accessors_reject_cells_just_past_the_edge() {
+        let mut grid = Grid::<bool>::new(4, 3);
+        for &(x, y) in &[(4, 0), (0, 3), (4, 3), (usize::MAX, 0)] {
+            let err = OutOfBounds {
+                x,
+                y,
+                width: 4,
+                height: 3,
+            };
+            assert_eq!(grid.get_checked(x, y), Err(err));
+            assert_eq!(grid.set_checked(x, y, true), Err(err));
+        }
+        assert_eq!(grid.count_true(), 0);
+        assert_eq!(
+            grid.get_checked(4, 0).unwrap_err().to_string(),
+            "cell (4, 0) is outside of the 4x3 grid"
+        );
+    }
+
+    fn numbered(width: usize, height: usize) -> Grid<usize> {
+        Grid::from_fn(width, height, |x, y| 1 + y * width + x)
+    }
+
+    fn values(grid: &Grid<usize>) -> Vec<usize> {
+        grid.iter_rowmajor().map(|(_, _, value)| value).collect()
+    }
+
+    #[test]
+    fn resize_grows_and_fills_new_cells() {
+        let mut grid = numbered(2, 2);
+        grid.resize(3, 4, 0);
+
+        assert_eq!((grid.width(), grid.height()), (3, 4));
+        for (x, y) in grid.keys() {
+            let expected = if x < 2 && y < 2 { 1 + y * 2 + x } else { 0 };
+            assert_eq!(grid.get(x, y), expected, "cell ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn resize_shrinks_and_keeps_the_overlap() {
+        let mut grid = numbered(4, 3);
+        grid.resize(2, 2, 0);
+
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        assert_eq!(values(&grid), vec![1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn resize_can_grow_one_axis_while_shrinking_the_other() {
+        let mut grid = numbered(3, 2);
+        grid.resize(2, 3, 9);
+        assert_eq!(values(&grid), vec![1, 2, 4, 5, 9, 9]);
+    }
+
+    #[test]
+    fn iter_mut_doubles_every_weight() {
+        let mut grid = numbered(3, 2);
+        for (_, _, weight) in grid.iter_mut() {
+            *weight *= 2;
+        }
+        assert_eq!(values(&grid), vec![2, 4, 6, 8, 10, 12]);
+    }
+
+    #[test]
+    fn iter_mut_yields_the_coordinates_of_each_cell() {
+        let mut grid = Grid::<(usize, usize)>::new(2, 3);
+        for (x, y, cell) in grid.iter_mut() {
+            *cell = (x, y);
+        }
+        assert!(grid.keys().all(|(x, y)| grid.get(x, y) == (x, y)));
+    }
+
+    // Two chambers of 2x3 cells each, split by a wall in the middle column.
+    fn two_chambers() -> Grid<bool> {
+        Grid::from_fn(5, 3, |x, _| x == 2)
+    }
+
+    #[test]
+    fn reachable_from_stays_in_its_chamber() {
+        let grid = two_chambers();
+        let left = grid.reachable_from((0, 0));
+        let right = grid.reachable_from((4, 2));
+
+        assert_eq!(left.len(), 6);
+        assert!(left.iter().all(|&(x, _)| x < 2));
+        assert_eq!(right.len(), 6);
+        assert!(right.iter().all(|&(x, _)| x > 2));
+    }
+
+    #[test]
+    fn reachable_from_crosses_an_opened_wall() {
+        let mut grid = two_chambers();
+        grid.set(2, 1, false);
+        let reachable = grid.reachable_from((0, 0));
+        assert_eq!(reachable.len(), 13);
+        assert!(reachable.contains(&(4, 2)));
+    }
+
+    #[test]
+    fn distances_between_a_cell_and_itself_are_zero() {
+        assert_eq!(Grid::manhattan((3, 4), (3, 4)), 0);
+        assert_eq!(Grid::euclidean((3, 4), (3, 4)), 0.);
+    }
+
+    #[test]
+    fn distances_along_an_axis_agree() {
+        assert_eq!(Grid::manhattan((1, 2), (6, 2)), 5);
+        assert_eq!(Grid::euclidean((1, 2), (6, 2)), 5.);
+        assert_eq!(Grid::manhattan((0, 7), (0, 3)), 4);
+    }
+
+    #[test]
+    fn distances_on_a_diagonal() {
+        assert_eq!(Grid::manhattan((0, 0), (3, 4)), 7);
+        assert_eq!(Grid::manhattan((3, 4), (0, 0)), 7);
+        assert_eq!(Grid::euclidean((0, 0), (3, 4)), 5.);
+        assert!((Grid::euclidean((2, 2), (3, 3)) - 2f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn line_connects_distant_cells_without_gaps() {
+        let grid = Grid::<bool>::new(8, 8);
+        let cells: Vec<_> = grid.line((0, 0), (6, 3)).collect();
+        assert_eq!(cells.first(), Some(&(0, 0)));
+        assert_eq!(cells.last(), Some(&(6, 3)));
+        assert_eq!(cells.len(), 7);
+        for pair in cells.windows(2) {
+            assert!(Connectivity::Eight.are_adjacent(pair[0], pair[1]), "gap in {:?}", cells);
+        }
+    }
+
+    #[test]
+    fn line_runs_in_both_directions() {
+        let grid = Grid::<bool>::new(8, 8);
+        let forward: Vec<_> = grid.line((1, 5), (4, 5)).collect();
+        let mut backward: Vec<_> = grid.line((4, 5), (1, 5)).collect();
+        backward.reverse();
+        assert_eq!(forward, vec![(1, 5), (2, 5), (3, 5), (4, 5)]);
+        assert_eq!(forward, backward);
+        assert_eq!(grid.line((2, 2), (2, 2)).collect::<Vec<_>>(), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn vertical_and_diagonal_lines() {
+        let grid = Grid::<bool>::new(5, 5);
+        assert_eq!(grid.line((3, 4), (3, 1)).collect::<Vec<_>>(), vec![(3, 4), (3, 3), (3, 2), (3, 1)]);
+        assert_eq!(grid.line((0, 3), (3, 0)).collect::<Vec<_>>(), vec![(0, 3), (1, 2), (2, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn steep_line_visits_every_row_once() {
+        let grid = Grid::<bool>::new(8, 8);
+        let cells: Vec<_> = grid.line((1, 0), (3, 6)).collect();
+        assert_eq!(cells.iter().map(|&(_, y)| y).collect::<Vec<_>>(), (0..7).collect::<Vec<_>>());
+        assert!(cells.iter().all(|&(x, _)| (1..=3).contains(&x)));
+        assert_eq!(cells.last(), Some(&(3, 6)));
+    }
+
+    #[test]
+    fn line_between_opposite_corners() {
+        let grid = Grid::<bool>::new(4, 4);
+        let cells: Vec<_> = grid.line((0, 0), (3, 3)).collect();
+        assert_eq!(cells, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn line_is_clipped_to_the_grid() {
+        let grid = Grid::<bool>::new(4, 3);
+        assert_eq!(grid.line((2, 1), (7, 1)).collect::<Vec<_>>(), vec![(2, 1), (3, 1)]);
+        assert_eq!(grid.line((5, 5), (9, 9)).count(), 0);
+    }
+
+    #[test]
+    fn count_true_and_density_of_a_partly_walled_grid() {
+        let grid = Grid::from_fn(4, 2, |x, y| x == y);
+        assert_eq!(grid.count_true(), 2);
+        assert_eq!(grid.density(), 0.25);
+    }
+
+    #[test]
+    fn count_true_and_density_at_the_extremes() {
+        let open = Grid::<bool>::new(3, 3);
+        assert_eq!((open.count_true(), open.density()), (0, 0.));
+        let full = Grid::from_fn(3, 3, |_, _| true);
+        assert_eq!((full.count_true(), full.density()), (9, 1.));
+    }
+
+    #[test]
+    fn density_of_an_empty_grid_is_zero() {
+        for &(width, height) in &[(0, 0), (0, 5), (5, 0)] {
+            let grid = Grid::<bool>::new(width, height);
+            assert_eq!(grid.count_true(), 0);
+            assert_eq!(grid.density(), 0.);
+        }
+    }
+
+    fn filled(probability: f32, seed: u64) -> Grid<bool> {
+        let mut grid = Grid::new(100, 100);
+        grid.random_fill(probability, seed);
+        grid
+    }
+
+    #[test]
+    fn random_fill_is_deterministic_for_a_seed() {
+        assert_eq!(filled(0.3, 42), filled(0.3, 42));
+        assert_ne!(filled(0.3, 42), filled(0.3, 43));
+    }
+
+    #[test]
+    fn random_fill_roughly_matches_the_probability() {
+        for &probability in &[0.1, 0.3, 0.5, 0.9] {
+            let density = filled(probability, 7).density();
+            assert!((density - probability).abs() < 0.03, "{} for {}", density, probability);
+        }
+    }
+
+    #[test]
+    fn random_fill_clamps_the_probability() {
+        assert_eq!(filled(-1., 1).count_true(), 0);
+        assert_eq!(filled(2., 1).count_true(), 100 * 100);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn from_luma_image_thresholds_a_tiny_image() {
+        // 3x2 pixels, the top row is dark in the middle.
+        let pixels = vec![255, 0, 255, 200, 255, 100];
+        let image = image::GrayImage::from_raw(3, 2, pixels).unwrap();
+        let grid = Grid::from_luma_image(&image);
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        // The top row of the image is the top of the grid.
+        let walls: Vec<_> = grid.keys().filter(|&(x, y)| grid.get(x, y)).collect();
+        assert_eq!(walls, vec![(1, 1), (2, 0)]);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn from_luma_image_scales_down_large_images() {
+        let image = image::GrayImage::from_fn(400, 100, |x, _| image::Luma([if x < 200 { 0 } else { 255 }]));
+        let grid = Grid::from_luma_image(&image);
+
+        assert_eq!((grid.width(), grid.height()), (MAX_IMAGE_GRID_SIZE as usize, 25));
+        assert!(grid.get(0, 0) && grid.get(40, 24));
+        assert!(!grid.get(60, 0) && !grid.get(99, 24));
+    }
+
+    fn hash_of(grid: &Grid<bool>) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        grid.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn independently_built_grids_are_equal_and_hash_alike() {
+        let mut painted = Grid::new(4, 3);
+        painted.set(1, 2, true);
+        painted.set(3, 0, true);
+        let generated = Grid::from_fn(4, 3, |x, y| (x, y) == (1, 2) || (x, y) == (3, 0));
+
+        assert_eq!(painted, generated);
+        assert_eq!(hash_of(&painted), hash_of(&generated));
+    }
+
+    #[test]
+    fn grids_differing_in_a_cell_or_shape_are_not_equal() {
+        let grid = Grid::<bool>::new(4, 3);
+        let mut walled = grid.clone();
+        walled.set(0, 0, true);
+        assert_ne!(grid, walled);
+        // Same cells, laid out differently.
+        assert_ne!(grid, Grid::new(3, 4));
+        assert_ne!(grid, Grid::new(6, 2));
+    }
+
+    #[test]
+    fn map_converts_walls_to_bytes_and_keeps_the_size() {
+        let mut walls = Grid::new(3, 2);
+        walls.set(0, 0, true);
+        walls.set(2, 1, true);
+
+        let bytes: Grid<u8> = walls.map(|wall| if wall { 255 } else { 0 });
+        assert_eq!((bytes.width(), bytes.height()), (3, 2));
+        for (x, y) in walls.keys() {
+            assert_eq!(bytes.get(x, y), if walls.get(x, y) { 255 } else { 0 });
+        }
+        assert_eq!(bytes.map(|value| value > 0), walls);
+    }
+
+    #[test]
+    fn map_of_an_empty_grid_is_empty() {
+        let grid: Grid<bool> = Grid::new(0, 4);
+        let mapped = grid.map(u8::from);
+        assert_eq!((mapped.width(), mapped.height()), (0, 4));
+    }
+
+    #[test]
+    fn keys_walk_columns_and_keys_rowmajor_walk_rows() {
+        let grid = Grid::<bool>::new(2, 3);
+        assert_eq!(
+            grid.keys().collect::<Vec<_>>(),
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            grid.keys_rowmajor().collect::<Vec<_>>(),
+            vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn iter_and_iter_rowmajor_pair_cells_with_their_values() {
+        let grid = numbered(2, 3);
+        assert_eq!(grid.iter().map(|(_, _, value)| value).collect::<Vec<_>>(), vec![1, 3, 5, 2, 4, 6]);
+        assert_eq!(values(&grid), vec![1, 2, 3, 4, 5, 6]);
+        assert!(grid.iter_rowmajor().all(|(x, y, value)| value == grid.get(x, y)));
+    }
+
+    #[test]
+    fn corner_cuts_need_walls_on_both_sides() {
+        let mut grid = Grid::new(3, 3);
+        grid.set(1, 0, true);
+        assert!(!grid.is_corner_cut((0, 0), (1, 1)));
+        grid.set(0, 1, true);
+        assert!(grid.is_corner_cut((0, 0), (1, 1)));
+        assert!(grid.is_corner_cut((1, 1), (0, 0)));
+        // Orthogonal steps pass no corner.
+        assert!(!grid.is_corner_cut((1, 1), (1, 2)));
+    }
+
+    #[test]
+    fn moves_skip_walls_and_cut_corners() {
+        let mut grid = Grid::new(3, 3);
+        grid.set(1, 0, true);
+        grid.set(0, 1, true);
+        let moves = |x, y, connectivity| {
+            let mut moves: Vec<_> = grid.moves(x, y, connectivity).collect();
+            moves.sort();
+            moves
+        };
+        assert_eq!(moves(0, 0, Connectivity::Four), vec![]);
+        assert_eq!(moves(0, 0, Connectivity::Eight), vec![(1, 1)]);
+        assert_eq!(moves(0, 0, Connectivity::EightNoCornerCutting), vec![]);
+        assert_eq!(
+            moves(1, 1, Connectivity::EightNoCornerCutting),
+            vec![(0, 2), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn transforms_move_cells_to_the_expected_places() {
+        // 4 5 6
+        // 1 2 3
+        let grid = numbered(3, 2);
+        let check = |transformed: Grid<usize>, size, rows: Vec<usize>| {
+            assert_eq!((transformed.width(), transformed.height()), size);
+            assert_eq!(values(&transformed), rows);
+        };
+        check(grid.transpose(), (2, 3), vec![1, 4, 2, 5, 3, 6]);
+        check(grid.rotate90(), (2, 3), vec![4, 1, 5, 2, 6, 3]);
+        check(grid.flip_horizontal(), (3, 2), vec![3, 2, 1, 6, 5, 4]);
+        check(grid.flip_vertical(), (3, 2), vec![4, 5, 6, 1, 2, 3]);
+    }
+
+    #[test]
+    fn transforms_map_single_cells_like_whole_grids() {
+        let size = (3, 2);
+        assert_eq!(GridTransform::Transpose.apply_to_cell((2, 0), size), (0, 2));
+        assert_eq!(GridTransform::Rotate90.apply_to_cell((2, 0), size), (1, 2));
+        assert_eq!(GridTransform::FlipHorizontal.apply_to_cell((2, 0), size), (0, 0));
+        assert_eq!(GridTransform::FlipVertical.apply_to_cell((2, 0), size), (2, 1));
+    }
+}
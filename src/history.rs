@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit {
+    Wall {
+        x: usize,
+        y: usize,
+        before: bool,
+        after: bool,
+    },
+    Start {
+        before: (usize, usize),
+        after: (usize, usize),
+    },
+    Finish {
+        before: (usize, usize),
+        after: (usize, usize),
+    },
+}
+
+impl Edit {
+    pub fn inverted(self) -> Self {
+        match self {
+            Edit::Wall {
+                x,
+                y,
+                before,
+                after,
+            } => Edit::Wall {
+                x,
+                y,
+                before: after,
+                after: before,
+            },
+            Edit::Start { before, after } => Edit::Start {
+                before: after,
+                after: before,
+            },
+            Edit::Finish { before, after } => Edit::Finish {
+                before: after,
+                after: before,
+            },
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Edits are grouped into strokes (everything between mouse down and mouse up),
+// so a single undo reverts a whole drag.
+pub struct History {
+    undo_stack: VecDeque<Vec<Edit>>,
+    redo_stack: Vec<Vec<Edit>>,
+    stroke: Vec<Edit>,
+    max_depth: usize,
+}
+
+impl History {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            stroke: vec![],
+            max_depth,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.stroke.clear();
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        self.stroke.push(edit);
+    }
+
+    pub fn commit_stroke(&mut self) {
+        if self.stroke.is_empty() {
+            return;
+        }
+        let stroke = std::mem::replace(&mut self.stroke, vec![]);
+        self.undo_stack.push_back(stroke);
+        while self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self) -> Option<Vec<Edit>> {
+        self.commit_stroke();
+        let stroke = self.undo_stack.pop_back()?;
+        let edits = stroke.iter().rev().map(|edit| edit.inverted()).collect();
+        self.redo_stack.push(stroke);
+        Some(edits)
+    }
+
+    pub fn redo(&mut self) -> Option<Vec<Edit>> {
+        self.commit_stroke();
+        let stroke = self.redo_stack.pop()?;
+        let edits = stroke.clone();
+        self.undo_stack.push_back(stroke);
+        Some(edits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(x: usize, after: bool) -> Edit {
+        Edit::Wall {
+            x,
+            y: 0,
+            before: !after,
+            after,
+        }
+    }
+
+    #[test]
+    fn undo_reverts_a_whole_stroke_in_reverse() {
+        let mut history = History::new(10);
+        history.record(wall(0, true));
+        history.record(wall(1, true));
+        history.commit_stroke();
+
+        assert_eq!(history.undo(), Some(vec![wall(1, false), wall(0, false)]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_commits_the_stroke_in_progress() {
+        let mut history = History::new(10);
+        history.record(wall(0, true));
+        assert_eq!(history.undo(), Some(vec![wall(0, false)]));
+    }
+
+    #[test]
+    fn empty_strokes_are_not_recorded() {
+        let mut history = History::new(10);
+        history.record(wall(0, true));
+        history.commit_stroke();
+        history.commit_stroke();
+        assert_eq!(history.undo(), Some(vec![wall(0, false)]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn redo_replays_what_undo_reverted() {
+        let mut history = History::new(10);
+        let moves = vec![
+            Edit::Start {
+                before: (0, 0),
+                after: (1, 0),
+            },
+            wall(0, true),
+        ];
+        for &edit in &moves {
+            history.record(edit);
+        }
+        history.commit_stroke();
+
+        history.undo();
+        assert_eq!(history.redo(), Some(moves.clone()));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo().map(|edits| edits.len()), Some(2));
+    }
+
+    #[test]
+    fn new_stroke_clears_redo() {
+        let mut history = History::new(10);
+        history.record(wall(0, true));
+        history.commit_stroke();
+        history.undo();
+
+        history.record(wall(1, true));
+        history.commit_stroke();
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn oldest_strokes_are_evicted_past_max_depth() {
+        let mut history = History::new(2);
+        for x in 0..3 {
+            history.record(wall(x, true));
+            history.commit_stroke();
+        }
+
+        assert_eq!(history.undo(), Some(vec![wall(2, false)]));
+        assert_eq!(history.undo(), Some(vec![wall(1, false)]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let mut history = History::new(10);
+        history.record(wall(0, true));
+        history.commit_stroke();
+        history.undo();
+        history.record(wall(1, true));
+
+        history.clear();
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), None);
+    }
+}
@@ -0,0 +1,15 @@
+pub mod examples;
+pub mod grid;
+pub mod history;
+pub mod maze;
+pub mod pathfind;
+pub mod perf;
+pub mod pygrid;
+pub mod pywrappers;
+pub mod runner;
+pub mod scene;
+pub mod share;
+pub mod theme;
+
+#[cfg(target_arch = "wasm32")]
+pub mod ui;
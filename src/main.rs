@@ -1,23 +1,104 @@
-pub mod grid;
-pub mod pathfind;
-pub mod pywrappers;
-pub mod runner;
-pub mod scene;
+use pathfind_demo::grid::Grid;
+use pathfind_demo::runner::Runner;
+use pathfind_demo::scene::PathtfindScene;
+#[cfg(not(target_arch = "wasm32"))]
+use pathfind_demo::{examples, runner};
 
 #[cfg(target_arch = "wasm32")]
-pub mod ui;
-
-use grid::Grid;
-use runner::Runner;
-use scene::PathtfindScene;
+use pathfind_demo::ui;
 
 use std::rc::Rc;
 use std::cell::RefCell;
 
+// On native builds a PNG maze can be passed as the first argument.
+#[cfg(not(target_arch = "wasm32"))]
+fn initial_grid() -> Grid<bool> {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => return Grid::new(20, 20),
+    };
+    match Grid::from_image(&path) {
+        Ok(grid) if grid.width() * grid.height() >= 2 => grid,
+        Ok(_) => {
+            eprintln!("Maze image {} is too small, starting with an empty grid", path);
+            Grid::new(20, 20)
+        }
+        Err(err) => {
+            eprintln!("Failed to load maze image {}: {}", path, err);
+            Grid::new(20, 20)
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn initial_grid() -> Grid<bool> {
+    Grid::new(20, 20)
+}
+
+// An image to draw behind the grid can follow the maze as the second argument.
+#[cfg(not(target_arch = "wasm32"))]
+fn initial_background() -> Option<runner::Image> {
+    let path = std::env::args().nth(2)?;
+    match image::open(&path) {
+        Ok(image) => {
+            let image = image.to_rgba();
+            let (width, height) = image.dimensions();
+            if width > u16::MAX as u32 || height > u16::MAX as u32 {
+                eprintln!("Background image {} is too large", path);
+                return None;
+            }
+            Some(runner::Image {
+                bytes: image.into_raw(),
+                width: width as u16,
+                height: height as u16,
+            })
+        }
+        Err(err) => {
+            eprintln!("Failed to load background image {}: {}", path, err);
+            None
+        }
+    }
+}
+
+// The Python file run with X, the BFS example by default.
+#[cfg(not(target_arch = "wasm32"))]
+fn initial_code() -> String {
+    let default = examples::EXAMPLES[0].code.to_owned();
+    let path = match std::env::args().nth(3) {
+        Some(path) => path,
+        None => return default,
+    };
+    std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("Failed to read code from {}: {}", path, err);
+        default
+    })
+}
+
 #[macroquad::main("PathfindDemo")]
 async fn main() {
-    let grid = Grid::<bool>::new(20, 20);
-    let scene = Rc::new(RefCell::new(PathtfindScene::new(grid, (0, 0), (19, 19))));
+    let mut grid = initial_grid();
+    let start = (0, 0);
+    let finish = (grid.width() - 1, grid.height() - 1);
+    grid.set(start.0, start.1, false);
+    grid.set(finish.0, finish.1, false);
+    let scene = Rc::new(RefCell::new(PathtfindScene::new(grid.clone(), start, finish)));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Some(background) = initial_background() {
+            scene.borrow_mut().set_background_image(background);
+        }
+        scene.borrow_mut().set_user_code(initial_code());
+
+        // Setting PATHFIND_SPLIT shows a second copy of the maze on the right,
+        // e.g. to run two searches side by side.
+        if std::env::var_os("PATHFIND_SPLIT").is_some() {
+            let other = Rc::new(RefCell::new(PathtfindScene::new(grid, start, finish)));
+            other.borrow_mut().set_user_code(initial_code());
+            let mut runner = Runner::split(scene, other);
+            return runner.run().await;
+        }
+    }
 
     #[cfg(target_arch = "wasm32")]
     ui::init(scene.clone());
@@ -0,0 +1,183 @@
+use crate::grid::Grid;
+
+use std::fmt;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+
+const WALL_CHAR: char = 'X';
+const OPEN_CHAR: char = '.';
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseMazeError {
+    Empty,
+    UnexpectedChar { line: usize, ch: char },
+    RaggedRow { line: usize, expected: usize, found: usize },
+    BadHeader { line: usize },
+    HeaderOutOfBounds { name: &'static str, x: usize, y: usize },
+    EndpointOnWall { name: &'static str, x: usize, y: usize },
+    SameEndpoints,
+}
+
+impl fmt::Display for ParseMazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMazeError::Empty => write!(f, "maze has no rows"),
+            ParseMazeError::UnexpectedChar { line, ch } => {
+                write!(f, "line {}: unexpected character {:?}", line, ch)
+            }
+            ParseMazeError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} cells, found {}",
+                line, expected, found
+            ),
+            ParseMazeError::BadHeader { line } => {
+                write!(f, "line {}: expected header value in the form x,y", line)
+            }
+            ParseMazeError::HeaderOutOfBounds { name, x, y } => {
+                write!(f, "{} ({}, {}) is outside of the maze", name, x, y)
+            }
+            ParseMazeError::EndpointOnWall { name, x, y } => {
+                write!(f, "{} ({}, {}) is on a wall", name, x, y)
+            }
+            ParseMazeError::SameEndpoints => write!(f, "start and finish are the same cell"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMazeError {}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A grid of walls together with optional metadata, stored as text:
+//
+//     # name: Corridor
+//     # start: 0,0
+//     # finish: 3,1
+//     X..X
+//     ....
+//
+// `X` is a wall and `.` is open. The first row of text is the top of the grid
+// (the highest y). Lines starting with `#` that are not known headers are
+// comments and blank lines are ignored.
+#[derive(Clone, Debug)]
+pub struct Maze {
+    pub name: Option<String>,
+    pub grid: Grid<bool>,
+    pub start: Option<(usize, usize)>,
+    pub finish: Option<(usize, usize)>,
+}
+
+fn parse_coordinates(value: &str, line: usize) -> Result<(usize, usize), ParseMazeError> {
+    let mut parts = value.split(',').map(|part| part.trim().parse::<usize>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+        _ => Err(ParseMazeError::BadHeader { line }),
+    }
+}
+
+impl FromStr for Maze {
+    type Err = ParseMazeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = None;
+        let mut start = None;
+        let mut finish = None;
+        let mut rows: Vec<Vec<bool>> = vec![];
+
+        for (i, line) in s.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                let mut header = comment.splitn(2, ':');
+                let key = header.next().unwrap_or("").trim();
+                let value = header.next().map(str::trim);
+                match (key, value) {
+                    ("name", Some(value)) => name = Some(value.to_owned()),
+                    ("start", Some(value)) => start = Some(parse_coordinates(value, line_number)?),
+                    ("finish", Some(value)) => finish = Some(parse_coordinates(value, line_number)?),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let row = line
+                .chars()
+                .map(|ch| match ch {
+                    WALL_CHAR => Ok(true),
+                    OPEN_CHAR => Ok(false),
+                    _ => Err(ParseMazeError::UnexpectedChar {
+                        line: line_number,
+                        ch,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(first) = rows.first() {
+                if first.len() != row.len() {
+                    return Err(ParseMazeError::RaggedRow {
+                        line: line_number,
+                        expected: first.len(),
+                        found: row.len(),
+                    });
+                }
+            }
+            rows.push(row);
+        }
+
+        if rows.is_empty() {
+            return Err(ParseMazeError::Empty);
+        }
+
+        let height = rows.len();
+        let mut grid = Grid::new(rows[0].len(), height);
+        for (i, row) in rows.into_iter().enumerate() {
+            for (x, is_wall) in row.into_iter().enumerate() {
+                grid.set(x, height - 1 - i, is_wall);
+            }
+        }
+
+        for &(header, cell) in &[("start", start), ("finish", finish)] {
+            if let Some((x, y)) = cell {
+                if x >= grid.width() || y >= grid.height() {
+                    return Err(ParseMazeError::HeaderOutOfBounds { name: header, x, y });
+                }
+            }
+        }
+
+        Ok(Self {
+            name,
+            grid,
+            start,
+            finish,
+        })
+    }
+}
+
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "# name: {}", name)?;
+        }
+        if let Some((x, y)) = self.start {
+            writeln!(f, "# start: {},{}", x, y)?;
+        }
+        if let Some((x, y)) = self.finish {
+            writeln!(f, "# finish: {},{}", x, y)?;
+        }
+        for y in (0..self.grid.height()).rev() {
+            for x in 0..self.grid.width() {
+                let ch = if self.grid.get(x, y) { WALL_CHAR } else { OPEN_CHAR };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
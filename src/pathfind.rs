@@ -1,32 +1,357 @@
-use crate::grid::Grid;
-use crate::pywrappers::{PyTuple2Wrapper, PyVecWrapper};
-use crate::scene::{colors, DrawCommand, Shape};
+#[cfg(not(target_arch = "wasm32"))]
+pub mod background;
+pub mod builtin;
+pub mod cache;
 
-use py::function::IntoFuncArgs;
-use py::pyobject::{IntoPyObject, ItemProtocol, TryFromObject, PyResult};
+use crate::grid::{Connectivity, CoordConvention, Grid};
+use crate::pygrid::{self, PyGridProxy, PyScratchGrid};
+use crate::pywrappers::{PyColorWrapper, PyDictWrapper, PyTuple2Wrapper, PyVecWrapper};
+use crate::scene::{colors, heatmap_color, Color, DrawCommand, Shape, CELL_SIZE};
+use crate::theme::Theme;
+
+use py::builtins::{PyDict, PyDictRef, PyStrRef};
+use py::function::{FuncArgs, IntoFuncArgs, OptionalArg};
+use py::pyobject::{BorrowValue, IntoPyObject, ItemProtocol, PyObjectRef, PyValue, TryFromObject, PyResult};
 use rustpython_vm as py;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::fmt;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// Stops a running search from another thread, checked by the VM hook on Python
+// calls and returns. Only native `BackgroundSearch` can use it: on wasm the
+// search blocks the one thread that would set it.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Searches report progress every this many Python calls and returns.
+pub const PROGRESS_INTERVAL: u64 = 10_000;
+
+// Called from inside a running search, on its thread, with the number of
+// Python calls and returns so far. There is no way to know how far a script
+// is from done, so this only shows that it is still making progress.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(u64) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(u64) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn call(&self, events: u64) {
+        (self.0)(events)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub connectivity: Connectivity,
+    pub path_color: Color,
+    // Color the path segments from the first color at the start to the second
+    // at the finish instead of using `path_color`, so its direction shows.
+    pub path_gradient: Option<(Color, Color)>,
+    pub cancel: Option<CancelToken>,
+    pub progress: Option<ProgressCallback>,
+    // Name of the global function user code is expected to define.
+    pub entry_point: String,
+    // Tint built-in expansions by the order the cells were expanded in.
+    pub heatmap: bool,
+    // When the finish can't be reached, built-in searches show the path to
+    // the closest cell they can reach instead of no path at all.
+    pub closest_approach: bool,
+    // Pass the grid to the entry point as nested lists, which costs a full
+    // copy into Python objects. Otherwise it gets the same `GridProxy` as the
+    // `GRID` global, whose lookups go straight to the Rust grid.
+    pub grid_as_list: bool,
+    // Order of the coordinates in every cell passed to or from Python.
+    pub convention: CoordConvention,
+    // Cost of entering each cell, for the reported path cost. Cells outside
+    // of it, or every cell without it, cost `DEFAULT_CELL_WEIGHT`.
+    pub weights: Option<Arc<Grid<usize>>>,
+    // Color every path segment by the weight of the cell it enters, which
+    // takes precedence over `path_gradient`. Needs `weights`.
+    pub color_by_weight: bool,
+}
+
+pub const DEFAULT_ENTRY_POINT: &str = "find_path";
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            connectivity: Connectivity::default(),
+            path_color: Theme::default().path,
+            path_gradient: None,
+            cancel: None,
+            progress: None,
+            entry_point: DEFAULT_ENTRY_POINT.to_owned(),
+            heatmap: false,
+            closest_approach: false,
+            grid_as_list: true,
+            convention: CoordConvention::default(),
+            weights: None,
+            color_by_weight: false,
+        }
+    }
+}
+
+pub const DEFAULT_CELL_WEIGHT: usize = 1;
+
+impl SearchOptions {
+    pub fn cell_weight(&self, (x, y): (usize, usize)) -> usize {
+        self.weights
+            .as_ref()
+            .and_then(|weights| weights.get_checked(x, y).ok())
+            .unwrap_or(DEFAULT_CELL_WEIGHT)
+    }
+}
+
+// What the user's `find_path` produced. `metadata` holds any extra fields of a
+// dict return value, already converted to strings for display. `cost` is the
+// `path_cost` of a validated path under `SearchOptions::weights`.
+#[derive(Clone)]
+pub struct UserSearch {
+    pub path: Option<Vec<(usize, usize)>>,
+    pub cost: Option<usize>,
+    pub metadata: Vec<(String, String)>,
+    pub draw_commands: Vec<DrawCommand>,
+}
+
+impl UserSearch {
+    pub fn summary(&self) -> String {
+        let cost = self.cost.map(|cost| format!("Path cost: {}", cost));
+        cost.into_iter()
+            .chain(Some(format_metadata(&self.metadata)).filter(|text| !text.is_empty()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Sum of the weights of the cells entered along the path, the first cell is
+// where the walk starts and costs nothing.
+pub fn path_cost(path: &[(usize, usize)], weight: impl Fn((usize, usize)) -> usize) -> usize {
+    path.iter().skip(1).map(|&cell| weight(cell)).sum()
+}
+
+fn format_metadata(metadata: &[(String, String)]) -> String {
+    metadata
+        .iter()
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 pub fn find_and_render_path(
     code: &str,
     grid: &Grid<bool>,
     start: (usize, usize),
     finish: (usize, usize),
-) -> Result<Vec<DrawCommand>, String> {
-    let (maybe_path, mut draw_commands) = find_path(code, grid, start, finish)?;
-    maybe_path.map(|path| {
-        draw_commands.push(DrawCommand::Clear);
-        draw_commands.push(DrawCommand::AddShape(Shape::SegmentedLine {
-            points: path,
-            width: 5.,
-            color: colors::LIME,
-        }));
-    });
-    Ok(draw_commands)
+    options: &SearchOptions,
+) -> Result<UserSearch, String> {
+    let mut search = find_path(code, grid, start, finish, options)?;
+    if let Some(path) = search.path.clone() {
+        validate_path(&path, grid, start, finish, options.connectivity)?;
+        search.cost = Some(path_cost(&path, |cell| options.cell_weight(cell)));
+        push_path(&mut search.draw_commands, path, options);
+    }
+    Ok(search)
+}
+
+pub const OPTIMAL_PATH_COLOR: Color = Color::new(1., 0., 1., 0.5);
+// Path line widths in scene units. The optimal path is drawn wider so the
+// user's path stays visible on top of it.
+pub const PATH_WIDTH: f32 = CELL_SIZE / 20.;
+pub const OPTIMAL_PATH_WIDTH: f32 = CELL_SIZE * 3. / 25.;
+
+pub struct Comparison {
+    pub user_steps: Option<usize>,
+    pub optimal_steps: Option<usize>,
+    pub metadata: Vec<(String, String)>,
+    pub draw_commands: Vec<DrawCommand>,
+}
+
+impl Comparison {
+    pub fn summary(&self) -> String {
+        let steps = self.steps_summary();
+        if self.metadata.is_empty() {
+            steps
+        } else {
+            format!("{}\n{}", steps, format_metadata(&self.metadata))
+        }
+    }
+
+    fn steps_summary(&self) -> String {
+        match (self.user_steps, self.optimal_steps) {
+            (Some(user), Some(optimal)) if user == optimal => {
+                format!("Your path: {} steps, Optimal: {} steps. Your path is optimal!", user, optimal)
+            }
+            (Some(user), Some(optimal)) => {
+                format!("Your path: {} steps, Optimal: {} steps", user, optimal)
+            }
+            (None, Some(optimal)) => format!(
+                "Your path: not found, Optimal: {} steps. A path exists but your algorithm missed it.",
+                optimal
+            ),
+            (Some(user), None) => format!(
+                "Your path: {} steps, but the built-in search found no path.",
+                user
+            ),
+            (None, None) => "No path exists, and your algorithm agrees.".to_owned(),
+        }
+    }
+}
+
+pub fn compare_with_optimal(
+    code: &str,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    options: &SearchOptions,
+) -> Result<Comparison, String> {
+    let UserSearch {
+        path: user_path,
+        metadata,
+        mut draw_commands,
+        ..
+    } = find_path(code, grid, start, finish, options)?;
+    if let Some(path) = &user_path {
+        validate_path(path, grid, start, finish, options.connectivity)?;
+    }
+    let optimal = builtin::find_path(builtin::Algorithm::AStar, grid, start, finish, options.connectivity);
+
+    let comparison_commands = optimal
+        .path
+        .iter()
+        .map(|path| (path, OPTIMAL_PATH_WIDTH, OPTIMAL_PATH_COLOR))
+        .chain(user_path.iter().map(|path| (path, PATH_WIDTH, options.path_color)))
+        .map(|(path, width, color)| {
+            DrawCommand::AddShape(Shape::SegmentedLine {
+                points: path.clone(),
+                width,
+                color,
+            })
+        });
+    draw_commands.push(DrawCommand::Clear);
+    draw_commands.extend(comparison_commands);
+
+    Ok(Comparison {
+        user_steps: user_path.as_ref().map(|path| path.len() - 1),
+        optimal_steps: optimal.stats().path_steps,
+        metadata,
+        draw_commands,
+    })
+}
+
+pub fn validate_path(
+    path: &[(usize, usize)],
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> Result<(), String> {
+    if path.first() != Some(&start) {
+        return Err(format!("Path must begin at the start cell {:?}", start));
+    }
+    if path.last() != Some(&finish) {
+        return Err(format!("Path must end at the finish cell {:?}", finish));
+    }
+    for &(x, y) in path {
+        match grid.get_checked(x, y) {
+            Ok(false) => (),
+            Ok(true) => return Err(format!("Path goes through a wall at {:?}", (x, y))),
+            Err(err) => return Err(format!("Path leaves the grid: {}", err)),
+        }
+    }
+    for (&from, &to) in path.iter().zip(path.iter().skip(1)) {
+        if !connectivity.are_adjacent(from, to) {
+            return Err(format!("Path jumps from {:?} to {:?}", from, to));
+        }
+        if !connectivity.allows_corner_cutting() && grid.is_corner_cut(from, to) {
+            return Err(format!("Path cuts the wall corner between {:?} and {:?}", from, to));
+        }
+    }
+    Ok(())
+}
+
+// The path is pushed one segment per command so the animation traces it from
+// start to finish instead of showing it all at once.
+fn push_path(draw_commands: &mut Vec<DrawCommand>, path: Vec<(usize, usize)>, options: &SearchOptions) {
+    draw_commands.push(DrawCommand::Clear);
+    match (options.weights.as_ref().filter(|_| options.color_by_weight), options.path_gradient) {
+        // The heaviest cell of the grid gets the warm end of the gradient.
+        (Some(weights), _) => {
+            let heaviest = weights.iter().map(|(_, _, weight)| weight).max().unwrap_or(0).max(1) as f32;
+            draw_commands.extend(path.windows(2).map(|segment| {
+                DrawCommand::AddShape(Shape::Line {
+                    from: segment[0],
+                    to: segment[1],
+                    width: PATH_WIDTH,
+                    color: heatmap_color(options.cell_weight(segment[1]) as f32 / heaviest),
+                })
+            }));
+        }
+        (None, Some((from, to))) => {
+            let count = path.len().saturating_sub(1) as f32;
+            draw_commands.extend(path.windows(2).enumerate().map(|(i, segment)| {
+                DrawCommand::AddShape(Shape::Line {
+                    from: segment[0],
+                    to: segment[1],
+                    width: PATH_WIDTH,
+                    color: lerp_color(from, to, (i as f32 + 0.5) / count),
+                })
+            }));
+        }
+        (None, None) => push_path_lines(draw_commands, &path, options.path_color),
+    }
+}
+
+fn push_path_lines(draw_commands: &mut Vec<DrawCommand>, path: &[(usize, usize)], color: Color) {
+    draw_commands.extend(path.windows(2).map(|segment| {
+        DrawCommand::AddShape(Shape::Line {
+            from: segment[0],
+            to: segment[1],
+            width: PATH_WIDTH,
+            color,
+        })
+    }));
+}
+
+// Mixes the two colors component-wise, `t` of 0 gives `from` and 1 gives `to`.
+// Values outside of [0, 1] are clamped.
+pub fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let t = if t.is_nan() { 0. } else { t.max(0.).min(1.) };
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    Color::new(
+        lerp(from.r, to.r),
+        lerp(from.g, to.g),
+        lerp(from.b, to.b),
+        lerp(from.a, to.a),
+    )
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -36,10 +361,17 @@ pub fn find_path(
     grid: &Grid<bool>,
     start: (usize, usize),
     finish: (usize, usize),
-) -> Result<(Option<Vec<(usize, usize)>>, Vec<DrawCommand>), String> {
+    options: &SearchOptions,
+) -> Result<UserSearch, String> {
+    if start == finish {
+        return Err("Start and finish are the same cell, there is no path to find.".to_owned());
+    }
     py::Interpreter::default().enter(|vm| {
-        try_find_path(vm, code, grid, start, finish)
+        try_find_path(vm, code, grid, start, finish, options)
             .map_err(|err| {
+                if options.cancel.as_ref().map_or(false, CancelToken::is_cancelled) {
+                    return "Search cancelled by user.".to_owned();
+                }
                 let mut traceback = Vec::<u8>::new();
                 py::exceptions::write_exception(&mut traceback, vm, &err)
                     .expect("failed to write exception");
@@ -48,86 +380,513 @@ pub fn find_path(
     })
 }
 
-fn try_find_path(
-    vm: &py::VirtualMachine,
-    code: &str,
-    grid: &Grid<bool>,
-    start: (usize, usize),
-    finish: (usize, usize),
-) -> PyResult<(Option<Vec<(usize, usize)>>, Vec<DrawCommand>)> {
+// Returns the line of user code where the error happened, taken from the
+// innermost `<embedded>` frame since helpers may appear below it.
+pub fn traceback_line(traceback: &str) -> Option<usize> {
+    traceback
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().strip_prefix("File \"<embedded>\", line ")?;
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .last()
+}
+
+thread_local! {
+    // The last source compiled on this thread with its bytecode. Live mode
+    // re-runs the same code on every maze edit, so this skips the compiler for
+    // all but the first of those runs. A new interpreter is created per run
+    // anyway, and the bytecode is independent of it.
+    //
+    // The cache is per thread: live mode searches on the thread that drives
+    // the scene, but every native background search gets a thread of its own
+    // and so always compiles.
+    static COMPILED_CODE: RefCell<Option<(String, py::bytecode::CodeObject)>> = RefCell::new(None);
+    static COMPILE_COUNT: Cell<usize> = Cell::new(0);
+}
+
+// How many times user code has gone through the compiler on this thread, i.e.
+// missed the cache.
+pub fn compile_count() -> usize {
+    COMPILE_COUNT.with(Cell::get)
+}
+
+fn compile_cached(vm: &py::VirtualMachine, code: &str) -> PyResult<py::bytecode::CodeObject> {
+    let cached = COMPILED_CODE.with(|compiled| match &*compiled.borrow() {
+        Some((source, code_obj)) if source == code => Some(code_obj.clone()),
+        _ => None,
+    });
+    if let Some(code_obj) = cached {
+        return Ok(code_obj);
+    }
+
     let code_obj = py::compile::compile(
         code,
         py::compile::Mode::Exec,
         "<embedded>".to_owned(),
         py::compile::CompileOpts::default(),
     ).map_err(|err| vm.new_syntax_error(&err))?;
+    COMPILE_COUNT.with(|count| count.set(count.get() + 1));
+    COMPILED_CODE.with(|compiled| *compiled.borrow_mut() = Some((code.to_owned(), code_obj.clone())));
+    Ok(code_obj)
+}
+
+fn try_find_path(
+    vm: &py::VirtualMachine,
+    code: &str,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    options: &SearchOptions,
+) -> PyResult<UserSearch> {
+    let code_obj = compile_cached(vm, code)?;
 
-    let (scope, commands) = prepare_scope(vm)?;
+    let (scope, commands, grid_proxy) = prepare_scope(vm, grid, start, finish, options)?;
+    sandbox(vm)?;
 
-    let py_path = run_python_code(code_obj, vm, grid, scope, start, finish)?;
+    let py_grid = if options.grid_as_list {
+        grid.clone().into_pyobject_with(options.convention, vm)
+    } else {
+        grid_proxy
+    };
+    let result = run_python_code(
+        code_obj,
+        vm,
+        py_grid,
+        scope,
+        options.convention.apply(start),
+        options.convention.apply(finish),
+        &options.entry_point,
+    )?;
+    let result = drain_generator(vm, result, &commands, options.convention)?;
 
+    // `find_path` may return either the path itself or a dict with the path
+    // under the "path" key and arbitrary extra fields to report.
+    let mut metadata = vec![];
+    let py_path = if result.payload_is::<PyDict>() {
+        let PyDictWrapper(entries) = PyDictWrapper::<PyStrRef, PyObjectRef>::try_from_object(vm, result)?;
+        let mut py_path = None;
+        for (key, value) in entries {
+            if key.borrow_value() == "path" {
+                py_path = Some(value);
+            } else {
+                let value = vm.to_str(&value)?;
+                metadata.push((key.borrow_value().to_owned(), value.borrow_value().to_owned()));
+            }
+        }
+        py_path.ok_or_else(|| {
+            vm.new_value_error(format!(
+                "Expected dict returned by '{}' to have a 'path' key",
+                options.entry_point
+            ))
+        })?
+    } else {
+        result
+    };
+
+    // A list or tuple keeps the error about its bad element, anything else is
+    // reported by type instead of the less helpful "not iterable".
+    let type_name = py_path.class().name.clone();
     let maybe_path = Option::<PyVecWrapper::<PyTuple2Wrapper<usize, usize>>>::try_from_object(vm, py_path)
         .map(|maybe_vec| {
             maybe_vec.map(|vec_wrapper| {
                 vec_wrapper
                     .0
                     .into_iter()
-                    .map(|tuple_wrapper| (tuple_wrapper.0, tuple_wrapper.1))
+                    .map(|tuple_wrapper| options.convention.apply((tuple_wrapper.0, tuple_wrapper.1)))
                     .collect()
             })
         })
-        .unwrap();
+        .map_err(|err| match type_name.as_str() {
+            "list" | "tuple" => err,
+            _ => vm.new_type_error(format!(
+                "'{}' must return a list of (x, y) tuples or None, got {}",
+                options.entry_point, type_name
+            )),
+        })?;
+
+    Ok(UserSearch {
+        path: maybe_path,
+        cost: None,
+        metadata,
+        draw_commands: Rc::try_unwrap(commands).unwrap().into_inner(),
+    })
+}
+
+const DEFAULT_LINE_WIDTH: f64 = PATH_WIDTH as f64;
 
-    Ok((maybe_path, Rc::try_unwrap(commands).unwrap().into_inner()))
+// `draw_line(from, to, width=5, color=(r, g, b[, a]))`
+#[derive(py::FromArgs)]
+struct DrawLineArgs {
+    #[pyarg(positional)]
+    from: PyTuple2Wrapper<usize, usize>,
+    #[pyarg(positional)]
+    to: PyTuple2Wrapper<usize, usize>,
+    #[pyarg(any, optional)]
+    width: OptionalArg<f64>,
+    #[pyarg(any, optional)]
+    color: OptionalArg<PyColorWrapper>,
 }
 
-fn prepare_scope(vm: &py::VirtualMachine) -> PyResult<(py::scope::Scope, Rc<RefCell<Vec<DrawCommand>>>)> {
+fn prepare_scope(
+    vm: &py::VirtualMachine,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    options: &SearchOptions,
+) -> PyResult<(py::scope::Scope, Rc<RefCell<Vec<DrawCommand>>>, PyObjectRef)> {
     let scope = vm.new_scope_with_builtins();
 
+    let helpers = register_helpers_module(vm)?;
+
+    let grid_proxy = PyGridProxy::new(Rc::new(grid.clone()), options.connectivity, options.convention)
+        .into_ref(vm)
+        .into_object();
+    scope.globals.set_item("GRID", grid_proxy.clone(), vm)?;
+    // Plain callables for scripts that only need wall lookups.
+    for &name in &["is_wall", "in_bounds"] {
+        scope.globals.set_item(name, vm.get_attribute(grid_proxy.clone(), name)?, vm)?;
+    }
+
+    // A fresh scratch grid per run, `set_cell` and `get_cell` are shortcuts
+    // for its methods.
+    let scratch = PyScratchGrid::new(grid.width(), grid.height(), options.convention)
+        .into_ref(vm)
+        .into_object();
+    scope.globals.set_item("SCRATCH", scratch.clone(), vm)?;
+    for &(global, method) in &[("set_cell", "set"), ("get_cell", "get")] {
+        scope.globals.set_item(global, vm.get_attribute(scratch.clone(), method)?, vm)?;
+    }
+
+    // Available without an import since nearly every search needs it.
+    scope.globals.set_item("reconstruct_path", helpers.get_item("reconstruct_path", vm)?, vm)?;
+
+    // Convenience constants mirroring the `find_path` arguments. They are set
+    // before the user code runs, so a script is free to shadow them.
+    scope.globals.set_item("WIDTH", grid.width().into_pyobject(vm), vm)?;
+    scope.globals.set_item("HEIGHT", grid.height().into_pyobject(vm), vm)?;
+    let convention = options.convention;
+    scope.globals.set_item("START", convention.apply(start).into_pyobject(vm), vm)?;
+    scope.globals.set_item("FINISH", convention.apply(finish).into_pyobject(vm), vm)?;
+
+    if options.cancel.is_some() || options.progress.is_some() {
+        let cancel = options.cancel.clone();
+        let progress = options.progress.clone();
+        let events = Cell::new(0);
+        install_hook(vm, move |vm| {
+            if cancel.as_ref().map_or(false, CancelToken::is_cancelled) {
+                return Err(vm.new_runtime_error("cancelled by user".to_owned()));
+            }
+            if let Some(progress) = &progress {
+                events.set(events.get() + 1);
+                if events.get() % PROGRESS_INTERVAL == 0 {
+                    progress.call(events.get());
+                }
+            }
+            Ok(())
+        });
+    }
+
     let commands = Rc::new(RefCell::new(vec![]));
 
-    let commands_inner = Rc::downgrade(&commands);
+    let push_command = command_sink(&commands);
     scope.globals.set_item(
         "draw_line",
         vm.ctx.new_function(
             "draw_line",
-            move |from: PyTuple2Wrapper<usize, usize>, to: PyTuple2Wrapper<usize, usize>| {
-                let PyTuple2Wrapper(x0, y0) = from;
-                let PyTuple2Wrapper(x1, y1) = to;
-                commands_inner
-                    .upgrade()
-                    .unwrap()
-                    .borrow_mut()
-                    .push(DrawCommand::AddShape(Shape::Line {
-                        from: (x0, y0),
-                        to: (x1, y1),
-                        width: 5.,
-                        color: colors::DARKGREEN,
-                    }));
+            move |args: DrawLineArgs, vm: &py::VirtualMachine| -> PyResult<()> {
+                let width = args.width.unwrap_or(DEFAULT_LINE_WIDTH);
+                if width.is_nan() || width <= 0. {
+                    return Err(vm.new_value_error("Expected line width to be positive".to_owned()));
+                }
+                let PyTuple2Wrapper(a0, b0) = args.from;
+                let PyTuple2Wrapper(a1, b1) = args.to;
+                push_command(DrawCommand::AddShape(Shape::Line {
+                    from: convention.apply((a0, b0)),
+                    to: convention.apply((a1, b1)),
+                    width: width as f32,
+                    color: args.color.into_option().map_or(colors::DARKGREEN, |color| color.0),
+                }));
+                Ok(())
+            }
+        ),
+        vm,
+    )?;
+
+    let push_command = command_sink(&commands);
+    scope.globals.set_item(
+        "draw_square",
+        vm.ctx.new_function(
+            "draw_square",
+            // The optional intensity selects a color from the heatmap gradient.
+            move |cell: PyTuple2Wrapper<usize, usize>, intensity: OptionalArg<f64>| {
+                let (x, y) = convention.apply((cell.0, cell.1));
+                push_command(DrawCommand::AddShape(Shape::Square {
+                    x,
+                    y,
+                    color: colors::SKYBLUE,
+                    intensity: intensity.into_option().map(|intensity| intensity as f32),
+                }));
+            }
+        ),
+        vm,
+    )?;
+
+    let push_command = command_sink(&commands);
+    scope.globals.set_item(
+        "draw_circle",
+        vm.ctx.new_function(
+            "draw_circle",
+            move |cell: PyTuple2Wrapper<usize, usize>, r: f64, vm: &py::VirtualMachine| -> PyResult<()> {
+                if r.is_nan() || r <= 0. {
+                    return Err(vm.new_value_error("Expected circle radius to be positive".to_owned()));
+                }
+                let (x, y) = convention.apply((cell.0, cell.1));
+                push_command(DrawCommand::AddShape(Shape::Circle {
+                    x,
+                    y,
+                    r: r as f32,
+                    color: colors::DARKGREEN,
+                }));
+                Ok(())
             }
         ),
         vm,
     )?;
 
-    Ok((scope, commands))
+    let push_command = command_sink(&commands);
+    scope.globals.set_item(
+        "draw_text",
+        vm.ctx.new_function(
+            "draw_text",
+            move |cell: PyTuple2Wrapper<usize, usize>, text: PyStrRef| {
+                let (x, y) = convention.apply((cell.0, cell.1));
+                push_command(DrawCommand::AddShape(Shape::Text {
+                    x,
+                    y,
+                    text: text.borrow_value().to_owned(),
+                    color: colors::BLACK,
+                }));
+            }
+        ),
+        vm,
+    )?;
+
+    let push_command = command_sink(&commands);
+    scope.globals.set_item(
+        "annotate",
+        vm.ctx.new_function(
+            "annotate",
+            // Shows `str(value)` in the cell, a later annotation replaces it.
+            move |a: usize, b: usize, value: PyObjectRef, vm: &py::VirtualMachine| -> PyResult<()> {
+                let (x, y) = convention.apply((a, b));
+                let text = vm.to_str(&value)?.borrow_value().to_owned();
+                push_command(DrawCommand::AddShape(Shape::Annotation { x, y, text }));
+                Ok(())
+            }
+        ),
+        vm,
+    )?;
+
+    Ok((scope, commands, grid_proxy))
+}
+
+// The hook piggybacks on the tracing machinery, so it runs on every call
+// and return, which is frequent enough to interrupt any real search.
+fn install_hook(vm: &py::VirtualMachine, hook: impl Fn(&py::VirtualMachine) -> PyResult<()> + 'static) {
+    let trace_func = vm.ctx.new_function(
+        "<hook>",
+        move |_args: FuncArgs, vm: &py::VirtualMachine| -> PyResult<()> { hook(vm) },
+    );
+    vm.trace_func.replace(trace_func);
+    vm.use_tracing.set(true);
+}
+
+const HELPERS_MODULE_NAME: &str = "pathfind";
+const HELPERS_MODULE_SOURCE: &str = include_str!("python/pathfind.py");
+
+// Returns the dict of the module, which stays shared with `sys.modules`.
+fn register_helpers_module(vm: &py::VirtualMachine) -> PyResult<PyDictRef> {
+    let code_obj = py::compile::compile(
+        HELPERS_MODULE_SOURCE,
+        py::compile::Mode::Exec,
+        format!("<{}>", HELPERS_MODULE_NAME),
+        py::compile::CompileOpts::default(),
+    ).map_err(|err| vm.new_syntax_error(&err))?;
+
+    let dict = vm.ctx.new_dict();
+    dict.set_item("__name__", vm.ctx.new_str(HELPERS_MODULE_NAME), vm)?;
+    vm.run_code_obj(
+        vm.new_code_object(code_obj),
+        py::scope::Scope::with_builtins(None, dict.clone(), vm),
+    )?;
+
+    dict.set_item("GridProxy", pygrid::make_class(vm).into_object(), vm)?;
+    dict.set_item("ScratchGrid", pygrid::make_scratch_class(vm).into_object(), vm)?;
+    dict.set_item(
+        "manhattan",
+        vm.ctx.new_function(
+            "manhattan",
+            |a: PyTuple2Wrapper<usize, usize>, b: PyTuple2Wrapper<usize, usize>| {
+                Grid::manhattan((a.0, a.1), (b.0, b.1))
+            },
+        ),
+        vm,
+    )?;
+
+    let module = vm.new_module(HELPERS_MODULE_NAME, dict.clone());
+    vm.get_attribute(vm.sys_module.clone(), "modules")?
+        .set_item(HELPERS_MODULE_NAME, module, vm)?;
+    Ok(dict)
+}
+
+const ALLOWED_MODULES: &[&str] = &[
+    "collections",
+    "functools",
+    "heapq",
+    "itertools",
+    "math",
+    HELPERS_MODULE_NAME,
+];
+
+// Modules other than the allowed ones that stay importable once they are
+// loaded. Scripts see the same builtins through their globals anyway.
+const PRELOADED_MODULES_KEPT: &[&str] = &["builtins"];
+
+fn is_module_allowed(name: &str) -> bool {
+    let top_level = name.split('.').next().unwrap_or("");
+    ALLOWED_MODULES.contains(&top_level)
+}
+
+// Every import once this has run is checked against `ALLOWED_MODULES`, no
+// matter which frame asks: scripts can make frames with fresh globals through
+// `exec` and `eval`, so the frame can't tell user code apart. Trusted code
+// runs before the sandbox is set up.
+fn sandbox(vm: &py::VirtualMachine) -> PyResult<()> {
+    // Load the allowed modules (and whatever they depend on) before any
+    // restrictions are in place.
+    for module in ALLOWED_MODULES {
+        vm.import(module, None, 0)?;
+    }
+
+    // The import statement returns modules that are already in `sys.modules`
+    // without calling `__import__`, so all the others loaded by now are
+    // poisoned with `None`, which makes importing them fail.
+    let sys_modules = PyDictRef::try_from_object(vm, vm.get_attribute(vm.sys_module.clone(), "modules")?)?;
+    let loaded: Vec<PyStrRef> = (&sys_modules)
+        .into_iter()
+        .filter_map(|(name, _)| PyStrRef::try_from_object(vm, name).ok())
+        .collect();
+    for name in loaded {
+        let name = name.borrow_value();
+        if !is_module_allowed(name) && !PRELOADED_MODULES_KEPT.contains(&name) {
+            sys_modules.set_item(name, vm.ctx.none(), vm)?;
+        }
+    }
+
+    let original_import = vm.get_attribute(vm.builtins.clone(), "__import__")?;
+    vm.set_attr(
+        &vm.builtins,
+        "__import__",
+        vm.ctx.new_function(
+            "__import__",
+            move |args: FuncArgs, vm: &py::VirtualMachine| -> PyResult {
+                let name = match args.args.first() {
+                    Some(name) => PyStrRef::try_from_object(vm, name.clone())?,
+                    None => return Err(vm.new_type_error("__import__() missing module name".to_owned())),
+                };
+                if !is_module_allowed(name.borrow_value()) {
+                    return Err(vm.new_exception_msg(
+                        vm.ctx.exceptions.import_error.clone(),
+                        format!(
+                            "import of '{}' is not allowed; available modules: {}",
+                            name.borrow_value(),
+                            ALLOWED_MODULES.join(", "),
+                        ),
+                    ));
+                }
+                vm.invoke(&original_import, args)
+            },
+        ),
+    )?;
+
+    vm.set_attr(
+        &vm.builtins,
+        "open",
+        vm.ctx.new_function(
+            "open",
+            |_args: FuncArgs, vm: &py::VirtualMachine| -> PyResult {
+                Err(vm.new_exception_msg(
+                    vm.ctx.exceptions.permission_error.clone(),
+                    "open() is not available in the sandbox".to_owned(),
+                ))
+            },
+        ),
+    )?;
+
+    Ok(())
+}
+
+fn command_sink(commands: &Rc<RefCell<Vec<DrawCommand>>>) -> impl Fn(DrawCommand) {
+    let commands = Rc::downgrade(commands);
+    move |command| commands.upgrade().unwrap().borrow_mut().push(command)
+}
+
+const YIELDED_CELL_COLOR: Color = colors::GOLD;
+
+// A `find_path` written as a generator yields the cells to show at each step,
+// which are drawn after whatever the draw functions added since the previous
+// yield. Its `return` value is then treated like the result of a plain
+// function. Anything other than a generator, including other iterators such
+// as `iter(path)`, is passed through unchanged.
+fn drain_generator(
+    vm: &py::VirtualMachine,
+    result: PyObjectRef,
+    commands: &Rc<RefCell<Vec<DrawCommand>>>,
+    convention: CoordConvention,
+) -> PyResult {
+    if !vm.isinstance(&result, &vm.ctx.types.generator_type)? {
+        return Ok(result);
+    }
+    loop {
+        let step = match vm.call_method(&result, "__next__", ()) {
+            Ok(step) => step,
+            Err(err) if vm.isinstance(err.as_object(), &vm.ctx.exceptions.stop_iteration)? => {
+                return vm.get_attribute(err.into_object(), "value");
+            }
+            Err(err) => return Err(err),
+        };
+        let cells = Option::<PyVecWrapper<PyTuple2Wrapper<usize, usize>>>::try_from_object(vm, step)?;
+        for PyTuple2Wrapper(a, b) in cells.map_or(vec![], |cells| cells.0) {
+            let (x, y) = convention.apply((a, b));
+            commands.borrow_mut().push(DrawCommand::AddShape(Shape::Square {
+                x,
+                y,
+                color: YIELDED_CELL_COLOR,
+                intensity: None,
+            }));
+        }
+    }
 }
 
 fn run_python_code(
     code_obj: py::bytecode::CodeObject,
     vm: &py::VirtualMachine,
-    grid: &Grid<bool>,
+    py_grid: PyObjectRef,
     scope: py::scope::Scope,
     start: (usize, usize),
     finish: (usize, usize),
+    entry_point: &str,
 ) -> py::pyobject::PyResult {
     let code_obj = vm.new_code_object(code_obj.clone());
     vm.run_code_obj(code_obj, scope.clone())?;
 
-    let py_grid = grid.clone().into_pyobject(vm);
-
-    let find_path_item = scope.globals.get_item("find_path", vm)?;
+    let expected_function = || vm.new_type_error(format!("Expected '{}' to be a function", entry_point));
+    let find_path_item = scope.globals.get_item_option(entry_point, vm)?
+        .ok_or_else(expected_function)?;
     let find_path_func = find_path_item.downcast::<py::builtins::PyFunction>()
-        .map_err(|_| vm.new_type_error("Expected 'find_path' to be a function".to_owned()))?;
+        .map_err(|_| expected_function())?;
 
     py::slots::Callable::call(
         &find_path_func,
@@ -135,3 +894,869 @@ fn run_python_code(
         vm,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Strips the indentation shared by all non-blank lines, so the scripts
+    // below can be indented along with the test code.
+    fn dedent(code: &str) -> String {
+        let indent = code
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        code.lines()
+            .map(|line| line.get(indent..).unwrap_or("").trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn run(
+        code: &str,
+        grid: &Grid<bool>,
+        start: (usize, usize),
+        finish: (usize, usize),
+    ) -> Result<UserSearch, String> {
+        run_with(code, grid, start, finish, &SearchOptions::default())
+    }
+
+    fn run_with(
+        code: &str,
+        grid: &Grid<bool>,
+        start: (usize, usize),
+        finish: (usize, usize),
+        options: &SearchOptions,
+    ) -> Result<UserSearch, String> {
+        find_and_render_path(&dedent(code), grid, start, finish, options)
+    }
+
+    // The default convention is `RowCol`, so on a single row the scripts see
+    // cell (x, 0) as (0, x).
+    fn run_on_row(code: &str) -> Result<UserSearch, String> {
+        run(code, &Grid::new(3, 1), (0, 0), (2, 0))
+    }
+
+    const ROW_PATH: &[(usize, usize)] = &[(0, 0), (1, 0), (2, 0)];
+
+    fn assert_import_blocked(result: Result<UserSearch, String>, module: &str) {
+        match result {
+            Ok(_) => panic!("importing '{}' should have failed", module),
+            Err(traceback) => assert!(
+                traceback.contains(&format!("import of '{}' is not allowed", module)),
+                "unexpected error: {}",
+                traceback
+            ),
+        }
+    }
+
+    #[test]
+    fn allowed_modules_can_be_imported() {
+        let search = run_on_row(
+            "
+            import collections, functools, heapq, itertools, math
+            from pathfind import PriorityQueue, manhattan
+
+            def find_path(grid, start, finish):
+                queue = PriorityQueue()
+                queue.push(finish, manhattan(start, finish))
+                queue.push(start, 0)
+                return [queue.pop(), (0, 1), queue.pop()]
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    #[test]
+    fn blocked_module_import_fails() {
+        assert_import_blocked(
+            run_on_row(
+                "
+                import os
+
+                def find_path(grid, start, finish):
+                    return None
+                ",
+            ),
+            "os",
+        );
+    }
+
+    #[test]
+    fn preloaded_module_import_fails() {
+        assert!(run_on_row(
+            "
+            import _thread
+
+            def find_path(grid, start, finish):
+                return None
+            ",
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn exec_with_fresh_globals_cannot_import() {
+        assert_import_blocked(
+            run_on_row(
+                r#"
+                def find_path(grid, start, finish):
+                    exec("import os", {})
+                    return None
+                "#,
+            ),
+            "os",
+        );
+    }
+
+    #[test]
+    fn eval_with_fresh_globals_cannot_import() {
+        assert_import_blocked(
+            run_on_row(
+                r#"
+                def find_path(grid, start, finish):
+                    eval("__import__('subprocess')", {})
+                    return None
+                "#,
+            ),
+            "subprocess",
+        );
+    }
+
+    #[test]
+    fn open_is_not_available() {
+        let traceback = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                open("/etc/passwd")
+            "#,
+        )
+        .unwrap_err();
+        assert!(traceback.contains("open() is not available in the sandbox"), "{}", traceback);
+    }
+
+    #[test]
+    fn iterator_return_is_a_path() {
+        let search = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                return iter([(0, 0), (0, 1), (0, 2)])
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+        assert!(search.draw_commands.iter().all(|command| !matches!(
+            command,
+            DrawCommand::AddShape(Shape::Square { .. })
+        )));
+    }
+
+    #[test]
+    fn reversed_return_is_a_path() {
+        let search = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                return reversed([(0, 2), (0, 1), (0, 0)])
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    #[test]
+    fn generator_steps_are_drawn_before_the_path() {
+        let search = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                yield [(0, 1)]
+                return [(0, 0), (0, 1), (0, 2)]
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+        match search.draw_commands.first() {
+            Some(DrawCommand::AddShape(Shape::Square { x, y, color, .. })) => {
+                assert_eq!((*x, *y), (1, 0));
+                assert_eq!(*color, YIELDED_CELL_COLOR);
+            }
+            other => panic!("expected the yielded cell first, got {:?}", other),
+        }
+    }
+
+    // Scripts that differ only in a trailing comment, so that each test
+    // starts out with code the cache hasn't seen.
+    fn row_script(comment: &str) -> String {
+        format!(
+            "
+            def find_path(grid, start, finish):
+                return [(0, 0), (0, 1), (0, 2)]
+            # {}
+            ",
+            comment
+        )
+    }
+
+    #[test]
+    fn unchanged_code_is_compiled_once() {
+        let code = row_script("unchanged");
+        let before = compile_count();
+        run_on_row(&code).unwrap();
+        run_on_row(&code).unwrap();
+        assert_eq!(compile_count(), before + 1);
+    }
+
+    #[test]
+    fn changed_code_is_compiled_again() {
+        let (code, edited) = (row_script("original"), row_script("edited"));
+        let before = compile_count();
+        run_on_row(&code).unwrap();
+        run_on_row(&edited).unwrap();
+        run_on_row(&code).unwrap();
+        assert_eq!(compile_count(), before + 3);
+    }
+
+    #[test]
+    fn syntax_errors_are_not_cached() {
+        let before = compile_count();
+        assert!(run_on_row("def find_path(:").is_err());
+        assert!(run_on_row("def find_path(:").is_err());
+        assert_eq!(compile_count(), before);
+    }
+
+    // A 3x2 grid where the straight line along the bottom row passes through
+    // an expensive cell and the detour over the top row is cheap.
+    fn weighted_options() -> SearchOptions {
+        let mut weights = Grid::from_fn(3, 2, |_, _| DEFAULT_CELL_WEIGHT);
+        weights.set(1, 0, 10);
+        SearchOptions {
+            weights: Some(Arc::new(weights)),
+            ..SearchOptions::default()
+        }
+    }
+
+    fn run_weighted(path: &str, options: &SearchOptions) -> UserSearch {
+        let code = format!("def find_path(grid, start, finish):\n    return {}\n", path);
+        find_and_render_path(&code, &Grid::new(3, 2), (0, 0), (2, 0), options).unwrap()
+    }
+
+    #[test]
+    fn path_cost_sums_entered_cell_weights() {
+        let options = weighted_options();
+        let straight = run_weighted("[(0, 0), (0, 1), (0, 2)]", &options);
+        let detour = run_weighted("[(0, 0), (1, 0), (1, 1), (1, 2), (0, 2)]", &options);
+        assert_eq!(straight.cost, Some(11));
+        assert_eq!(detour.cost, Some(4));
+        assert_eq!(detour.summary(), "Path cost: 4");
+    }
+
+    #[test]
+    fn path_cost_without_weights_counts_steps() {
+        let search = run_weighted("[(0, 0), (1, 0), (1, 1), (1, 2), (0, 2)]", &SearchOptions::default());
+        assert_eq!(search.cost, Some(4));
+    }
+
+    #[test]
+    fn segments_are_colored_by_entered_cell_weight() {
+        let options = SearchOptions {
+            color_by_weight: true,
+            ..weighted_options()
+        };
+        let search = run_weighted("[(0, 0), (0, 1), (0, 2)]", &options);
+        let colors: Vec<Color> = search
+            .draw_commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::AddShape(Shape::Line { color, .. }) => Some(*color),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(colors, vec![heatmap_color(1.), heatmap_color(0.1)]);
+    }
+
+    #[test]
+    fn dijkstra_with_helpers_finds_shortest_path() {
+        // A wall in the middle column, open only at the top, forces a detour.
+        let mut grid = Grid::new(3, 3);
+        grid.set(1, 0, true);
+        grid.set(1, 1, true);
+        let search = run(
+            "
+            from pathfind import PriorityQueue, reconstruct_path
+
+            def find_path(grid, start, finish):
+                rows, cols = len(grid), len(grid[0])
+                dist = {start: 0}
+                came_from = {}
+                queue = PriorityQueue()
+                queue.push(start, 0)
+                while len(queue):
+                    cell = queue.pop()
+                    if cell == finish:
+                        break
+                    row, col = cell
+                    for nxt in [(row + 1, col), (row - 1, col), (row, col + 1), (row, col - 1)]:
+                        r, c = nxt
+                        if 0 <= r < rows and 0 <= c < cols and not grid[r][c]:
+                            d = dist[cell] + 1
+                            if nxt not in dist or d < dist[nxt]:
+                                dist[nxt] = d
+                                came_from[nxt] = cell
+                                queue.push(nxt, d)
+                return reconstruct_path(came_from, start, finish)
+            ",
+            &grid,
+            (0, 0),
+            (2, 0),
+        )
+        .unwrap();
+        let expected: &[(usize, usize)] = &[(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)];
+        assert_eq!(search.path.as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn helper_names_are_not_injected_into_the_scope() {
+        let search = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                for name in ["PriorityQueue", "euclidean"]:
+                    if name in globals():
+                        raise Exception(name + " should only be importable")
+                return [(0, 0), (0, 1), (0, 2)]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    // Walks the proxy's neighbors breadth first, so it only finds the way
+    // around the wall if `neighbors` skips walls and cells off the grid.
+    const PROXY_BFS: &str = "
+        from collections import deque
+
+        def find_path(grid, start, finish):
+            came_from = {start: None}
+            queue = deque([start])
+            while queue:
+                cell = queue.popleft()
+                if cell == finish:
+                    return reconstruct_path(came_from, start, finish)
+                for nxt in grid.neighbors(*cell):
+                    if nxt not in came_from:
+                        came_from[nxt] = cell
+                        queue.append(nxt)
+            return None
+        ";
+
+    #[test]
+    fn grid_proxy_neighbors_lead_around_walls() {
+        let mut grid = Grid::new(3, 2);
+        grid.set(1, 0, true);
+        let options = SearchOptions {
+            grid_as_list: false,
+            ..SearchOptions::default()
+        };
+        let search = run_with(PROXY_BFS, &grid, (0, 0), (2, 0), &options).unwrap();
+        let expected: &[(usize, usize)] = &[(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)];
+        assert_eq!(search.path.as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn grid_proxy_reports_size_and_walls() {
+        let mut grid = Grid::new(3, 2);
+        grid.set(1, 0, true);
+        let search = run(
+            r#"
+            def find_path(grid, start, finish):
+                assert (GRID.width, GRID.height) == (3, 2)
+                assert GRID.is_wall(0, 1) and is_wall(0, 1)
+                assert not GRID.is_wall(1, 1)
+                assert is_wall(-1, 0) and is_wall(2, 0)
+                assert in_bounds(1, 2) and not in_bounds(2, 0)
+                assert sorted(GRID.neighbors(1, 0)) == [(0, 0), (1, 1)]
+                return [(0, 0), (1, 0), (1, 1), (1, 2), (0, 2)]
+            "#,
+            &grid,
+            (0, 0),
+            (2, 0),
+        )
+        .unwrap();
+        assert_eq!(search.path.map(|path| path.len()), Some(5));
+    }
+
+    #[test]
+    fn size_and_endpoint_constants_match_the_arguments() {
+        let search = run(
+            "
+            def find_path(grid, start, finish):
+                assert (WIDTH, HEIGHT) == (5, 3)
+                assert (START, FINISH) == (start, finish) == ((0, 0), (0, 4))
+                return [(0, col) for col in range(WIDTH)]
+            ",
+            &Grid::new(5, 3),
+            (0, 0),
+            (4, 0),
+        )
+        .unwrap();
+        assert_eq!(search.path.map(|path| path.len()), Some(5));
+    }
+
+    #[test]
+    fn scripts_can_shadow_the_constants() {
+        let search = run_on_row(
+            "
+            WIDTH = 2
+
+            def find_path(grid, start, finish):
+                return [(0, col) for col in range(WIDTH + 1)]
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    #[test]
+    fn dict_return_carries_path_and_metadata() {
+        let search = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                return {"cost": 42, "path": [(0, 0), (0, 1), (0, 2)], "note": "straight"}
+            "#,
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+        assert_eq!(
+            search.metadata,
+            vec![("cost".to_owned(), "42".to_owned()), ("note".to_owned(), "straight".to_owned())]
+        );
+    }
+
+    #[test]
+    fn list_return_has_no_metadata() {
+        let search = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                return [(0, 0), (0, 1), (0, 2)]
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+        assert!(search.metadata.is_empty());
+    }
+
+    #[test]
+    fn dict_return_without_path_fails() {
+        let err = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                return {"cost": 42}
+            "#,
+        )
+        .err()
+        .expect("a dict without a path should be rejected");
+        assert!(
+            err.contains("Expected dict returned by 'find_path' to have a 'path' key"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn manhattan_is_importable_from_pathfind() {
+        let search = run_on_row(
+            "
+            from pathfind import manhattan
+
+            def find_path(grid, start, finish):
+                assert manhattan(start, finish) == 2
+                assert manhattan((0, 0), (3, 4)) == manhattan((3, 4), (0, 0)) == 7
+                return [(0, 0), (0, 1), (0, 2)]
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    fn solve_options() -> SearchOptions {
+        SearchOptions {
+            entry_point: "solve".to_owned(),
+            ..SearchOptions::default()
+        }
+    }
+
+    #[test]
+    fn named_entry_point_is_called() {
+        let search = run_with(
+            "
+            def find_path(grid, start, finish):
+                return None
+
+            def solve(grid, start, finish):
+                return [(0, 0), (0, 1), (0, 2)]
+            ",
+            &Grid::new(3, 1),
+            (0, 0),
+            (2, 0),
+            &solve_options(),
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    #[test]
+    fn missing_entry_point_is_named_in_the_error() {
+        let err = run_with(
+            "
+            def find_path(grid, start, finish):
+                return None
+            ",
+            &Grid::new(3, 1),
+            (0, 0),
+            (2, 0),
+            &solve_options(),
+        )
+        .err()
+        .expect("a missing entry point should be an error");
+        assert!(err.contains("Expected 'solve' to be a function"), "unexpected error: {}", err);
+    }
+
+    fn drawn_lines(search: &UserSearch) -> Vec<(f32, Color)> {
+        search
+            .draw_commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::AddShape(Shape::Line { width, color, .. }) => Some((*width, *color)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draw_line_accepts_a_custom_width_and_color() {
+        let search = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                draw_line((0, 0), (0, 1))
+                draw_line((0, 0), (0, 1), 1.5)
+                draw_line((0, 1), (0, 2), width=12, color=(1, 0, 0))
+                return None
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            drawn_lines(&search),
+            vec![
+                (PATH_WIDTH, colors::DARKGREEN),
+                (1.5, colors::DARKGREEN),
+                (12., Color::new(1., 0., 0., 1.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_line_rejects_non_positive_widths() {
+        for &width in &["0", "-3", "float('nan')"] {
+            let code = format!(
+                "def find_path(grid, start, finish):\n    draw_line((0, 0), (0, 1), {})\n",
+                width
+            );
+            let err = find_and_render_path(&code, &Grid::new(3, 1), (0, 0), (2, 0), &SearchOptions::default())
+                .err()
+                .expect("a bad width should be rejected");
+            assert!(err.contains("Expected line width to be positive"), "unexpected error: {}", err);
+        }
+    }
+
+    // A 3x2 grid with a single wall at x = 2, y = 0, searched from the bottom
+    // left corner to the top right one.
+    fn run_with_convention(code: &str, convention: CoordConvention) -> Result<UserSearch, String> {
+        let mut grid = Grid::new(3, 2);
+        grid.set(2, 0, true);
+        let options = SearchOptions {
+            convention,
+            ..SearchOptions::default()
+        };
+        run_with(code, &grid, (0, 0), (2, 1), &options)
+    }
+
+    const CORNER_PATH: &[(usize, usize)] = &[(0, 0), (0, 1), (1, 1), (2, 1)];
+
+    #[test]
+    fn row_col_convention_indexes_grid_by_row_then_column() {
+        let search = run_with_convention(
+            "
+            def find_path(grid, start, finish):
+                assert (len(grid), len(grid[0])) == (2, 3)
+                assert grid[0][2] and not grid[1][2]
+                assert (start, finish) == ((0, 0), (1, 2))
+                assert GRID.is_wall(0, 2) and not GRID.is_wall(1, 2)
+                return [(0, 0), (1, 0), (1, 1), (1, 2)]
+            ",
+            CoordConvention::RowCol,
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(CORNER_PATH));
+    }
+
+    #[test]
+    fn xy_convention_indexes_grid_by_x_then_y() {
+        let search = run_with_convention(
+            "
+            def find_path(grid, start, finish):
+                assert (len(grid), len(grid[0])) == (3, 2)
+                assert grid[2][0] and not grid[2][1]
+                assert (start, finish) == ((0, 0), (2, 1))
+                assert GRID.is_wall(2, 0) and not GRID.is_wall(2, 1)
+                return [(0, 0), (0, 1), (1, 1), (2, 1)]
+            ",
+            CoordConvention::XY,
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(CORNER_PATH));
+    }
+
+    #[test]
+    fn annotate_records_cell_texts_in_call_order() {
+        let search = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                annotate(0, 1, 3)
+                annotate(0, 2, "far")
+                annotate(0, 1, 2.5)
+                return None
+            "#,
+        )
+        .unwrap();
+        let annotations: Vec<_> = search
+            .draw_commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::AddShape(Shape::Annotation { x, y, text }) => Some(((*x, *y), text.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(annotations, vec![((1, 0), "3"), ((2, 0), "far"), ((1, 0), "2.5")]);
+    }
+
+    #[test]
+    fn lerp_color_mixes_and_clamps() {
+        let from = Color::new(0., 1., 0.2, 1.);
+        let to = Color::new(1., 0., 0.6, 0.5);
+        assert_eq!(lerp_color(from, to, 0.), from);
+        assert_eq!(lerp_color(from, to, 1.), to);
+        let middle = lerp_color(from, to, 0.5);
+        for &(actual, expected) in &[(middle.r, 0.5), (middle.g, 0.5), (middle.b, 0.4), (middle.a, 0.75)] {
+            assert!((actual - expected).abs() < 1e-6, "{:?}", middle);
+        }
+        assert_eq!(lerp_color(from, to, -3.), from);
+        assert_eq!(lerp_color(from, to, 7.), to);
+        assert_eq!(lerp_color(from, to, f32::NAN), from);
+    }
+
+    #[test]
+    fn gradient_path_goes_from_the_start_color_to_the_finish_color() {
+        let (from, to) = (Color::new(0., 0., 0., 1.), Color::new(1., 1., 1., 1.));
+        let options = SearchOptions {
+            path_gradient: Some((from, to)),
+            ..SearchOptions::default()
+        };
+        let mut commands = vec![];
+        push_path(&mut commands, vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)], &options);
+
+        let reds: Vec<f32> = commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::AddShape(Shape::Line { color, .. }) => Some(color.r),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reds, vec![0.125, 0.375, 0.625, 0.875]);
+
+        // Without a gradient every segment gets the plain path color.
+        let mut plain = vec![];
+        push_path(&mut plain, vec![(0, 0), (1, 0), (2, 0)], &SearchOptions::default());
+        assert!(plain[1..].iter().all(|command| matches!(
+            command,
+            DrawCommand::AddShape(Shape::Line { color, .. }) if *color == SearchOptions::default().path_color
+        )));
+    }
+
+    #[test]
+    fn bad_return_types_are_reported_instead_of_panicking() {
+        for &(value, type_name) in &[("42", "int"), ("1.5", "float"), ("'(0, 0)'", "str"), ("{1, 2}", "set")] {
+            let code = format!("def find_path(grid, start, finish):\n    return {}\n", value);
+            let traceback = run_on_row(&code).unwrap_err();
+            let expected = format!("'find_path' must return a list of (x, y) tuples or None, got {}", type_name);
+            assert!(traceback.contains(&expected), "{}", traceback);
+        }
+    }
+
+    #[test]
+    fn bad_return_errors_name_the_entry_point() {
+        let options = SearchOptions {
+            entry_point: "solve".to_owned(),
+            ..SearchOptions::default()
+        };
+        let traceback = run_with(
+            "def solve(grid, start, finish):\n    return 7\n",
+            &Grid::new(3, 1),
+            (0, 0),
+            (2, 0),
+            &options,
+        )
+        .unwrap_err();
+        assert!(traceback.contains("'solve' must return"), "{}", traceback);
+    }
+
+    #[test]
+    fn lists_with_a_bad_element_keep_the_element_error() {
+        let traceback = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                return [(0, 0), 5]
+            ",
+        )
+        .unwrap_err();
+        assert!(!traceback.contains("must return a list"), "{}", traceback);
+    }
+
+    #[test]
+    fn scratch_grid_reads_back_what_was_written() {
+        let search = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                set_cell(0, 2, 7)
+                SCRATCH.set(0, 0, -3)
+                SCRATCH.clear()
+                SCRATCH.set(0, 1, 5)
+                return {
+                    "path": [(0, 0), (0, 1), (0, 2)],
+                    "written": (SCRATCH.get(0, 1), get_cell(0, 2)),
+                    "size": (SCRATCH.width, SCRATCH.height),
+                }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            search.metadata,
+            vec![("written".to_owned(), "(5, 0)".to_owned()), ("size".to_owned(), "(3, 1)".to_owned())]
+        );
+    }
+
+    #[test]
+    fn scratch_grid_starts_empty_on_every_run() {
+        let code = r#"
+            def find_path(grid, start, finish):
+                seen = get_cell(0, 1)
+                set_cell(0, 1, seen + 1)
+                return {"path": [(0, 0), (0, 1), (0, 2)], "seen": seen}
+            "#;
+        for _ in 0..2 {
+            let search = run_on_row(code).unwrap();
+            assert_eq!(search.metadata, vec![("seen".to_owned(), "0".to_owned())]);
+        }
+    }
+
+    #[test]
+    fn scratch_grid_rejects_cells_outside_of_the_grid() {
+        let traceback = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                set_cell(1, 0, 1)
+            ",
+        )
+        .unwrap_err();
+        assert!(traceback.contains("IndexError"), "{}", traceback);
+        assert!(traceback.contains("outside of the grid"), "{}", traceback);
+    }
+
+    fn run_with_progress(code: &str) -> Vec<u64> {
+        let reports = Arc::new(std::sync::Mutex::new(vec![]));
+        let options = SearchOptions {
+            progress: Some(ProgressCallback::new({
+                let reports = reports.clone();
+                move |events| reports.lock().unwrap().push(events)
+            })),
+            ..SearchOptions::default()
+        };
+        run_with(code, &Grid::new(3, 1), (0, 0), (2, 0), &options).unwrap();
+        let reports = reports.lock().unwrap();
+        reports.clone()
+    }
+
+    #[test]
+    fn progress_is_reported_every_interval() {
+        let reports = run_with_progress(
+            "
+            def step():
+                pass
+
+            def find_path(grid, start, finish):
+                for _ in range(30000):
+                    step()
+                return None
+            ",
+        );
+        // Each call of `step` is a call and a return.
+        assert!(reports.len() >= 6, "{:?}", reports);
+        for (i, &events) in reports.iter().enumerate() {
+            assert_eq!(events, (i as u64 + 1) * PROGRESS_INTERVAL);
+        }
+    }
+
+    #[test]
+    fn short_searches_report_no_progress() {
+        let reports = run_with_progress("def find_path(grid, start, finish):\n    return None\n");
+        assert!(reports.is_empty(), "{:?}", reports);
+    }
+
+    #[test]
+    fn reconstruct_path_is_available_without_an_import() {
+        let search = run_on_row(
+            "
+            def find_path(grid, start, finish):
+                came_from = {}
+                frontier = [start]
+                while frontier:
+                    r, c = frontier.pop()
+                    for nxt in [(r, c - 1), (r, c + 1)]:
+                        if 0 <= nxt[1] < WIDTH and nxt != start and nxt not in came_from:
+                            came_from[nxt] = (r, c)
+                            frontier.append(nxt)
+                return reconstruct_path(came_from, start, finish)
+            ",
+        )
+        .unwrap();
+        assert_eq!(search.path.as_deref(), Some(ROW_PATH));
+    }
+
+    #[test]
+    fn reconstruct_path_returns_none_without_a_way_back() {
+        let search = run_on_row(
+            r#"
+            def find_path(grid, start, finish):
+                return {
+                    "path": [(0, 0), (0, 1), (0, 2)],
+                    "unreached": reconstruct_path({(0, 1): (0, 0)}, (0, 0), (0, 2)),
+                    "cycle": reconstruct_path({(0, 2): (0, 1), (0, 1): (0, 2)}, (0, 0), (0, 2)),
+                    "dead_end": reconstruct_path({(0, 2): (0, 1), (0, 1): None}, (0, 0), (0, 2)),
+                    "same_cell": reconstruct_path({}, (0, 1), (0, 1)),
+                }
+            "#,
+        )
+        .unwrap();
+        let value = |key: &str| {
+            search
+                .metadata
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(value("unreached"), Some("None"));
+        assert_eq!(value("cycle"), Some("None"));
+        assert_eq!(value("dead_end"), Some("None"));
+        assert_eq!(value("same_cell"), Some("[(0, 1)]"));
+    }
+}
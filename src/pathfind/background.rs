@@ -0,0 +1,133 @@
+use super::{find_and_render_path, CancelToken, ProgressCallback, SearchOptions, UserSearch};
+use crate::grid::Grid;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A user search running on its own thread, so a slow script doesn't freeze
+// the render loop. The Python VM isn't `Send`, but it never has to cross
+// threads: `find_path` creates the interpreter on the worker and drops it
+// there, only the inputs and the finished result are moved.
+pub struct BackgroundSearch {
+    receiver: mpsc::Receiver<Result<UserSearch, String>>,
+    cancel: CancelToken,
+    progress: Arc<AtomicU64>,
+}
+
+impl BackgroundSearch {
+    pub fn spawn(
+        code: String,
+        grid: Grid<bool>,
+        start: (usize, usize),
+        finish: (usize, usize),
+        options: SearchOptions,
+    ) -> Self {
+        let cancel = options.cancel.clone().unwrap_or_default();
+        let progress = Arc::new(AtomicU64::new(0));
+        let report = {
+            let progress = progress.clone();
+            let callback = options.progress.clone();
+            ProgressCallback::new(move |events| {
+                progress.store(events, Ordering::Relaxed);
+                if let Some(callback) = &callback {
+                    callback.call(events);
+                }
+            })
+        };
+        let options = SearchOptions {
+            cancel: Some(cancel.clone()),
+            progress: Some(report),
+            ..options
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = find_and_render_path(&code, &grid, start, finish, &options);
+            // Nobody is waiting any more if the search was dropped.
+            let _ = sender.send(result);
+        });
+        Self {
+            receiver,
+            cancel,
+            progress,
+        }
+    }
+
+    // Python calls and returns the worker had made at its last progress
+    // report, see `ProgressCallback`.
+    pub fn progress(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    // Returns the result once the worker is done, `None` while it still runs.
+    pub fn poll(&self) -> Option<Result<UserSearch, String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("The search thread panicked.".to_owned())),
+        }
+    }
+
+    // Asks the worker to stop at the next check of the cancel token.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, Instant};
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn spawn(code: &str) -> BackgroundSearch {
+        BackgroundSearch::spawn(code.to_owned(), Grid::new(3, 1), (0, 0), (2, 0), SearchOptions::default())
+    }
+
+    fn wait(search: &BackgroundSearch) -> Result<UserSearch, String> {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(result) = search.poll() {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "the search did not finish in time");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn poll_returns_the_result_once_the_worker_is_done() {
+        let search = spawn("def find_path(grid, start, finish):\n    return [(0, 0), (0, 1), (0, 2)]\n");
+        let result = wait(&search).unwrap();
+        assert_eq!(result.path, Some(vec![(0, 0), (1, 0), (2, 0)]));
+    }
+
+    #[test]
+    fn poll_is_empty_while_running_until_cancelled() {
+        // The cancel token is checked on Python calls, so the loop has to make some.
+        let search = spawn(
+            "def spin():\n    pass\n\n\
+             def find_path(grid, start, finish):\n    while True:\n        spin()\n",
+        );
+        let deadline = Instant::now() + TIMEOUT;
+        while search.progress() == 0 {
+            assert!(Instant::now() < deadline, "the search never reported progress");
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(search.poll().is_none());
+
+        search.cancel();
+        assert!(wait(&search).is_err());
+    }
+
+    #[test]
+    fn script_errors_come_back_as_results() {
+        let search = spawn("def find_path(grid, start, finish):\n    raise ValueError('nope')\n");
+        let traceback = wait(&search).err().expect("the script should have failed");
+        assert!(traceback.contains("nope"), "unexpected error: {}", traceback);
+    }
+}
@@ -0,0 +1,899 @@
+use super::{push_path, push_path_lines, SearchOptions};
+use crate::grid::{Connectivity, Grid};
+use crate::scene::{colors, Color, DrawCommand, Shape, CELL_SIZE};
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+pub const EXPANSION_COLOR: Color = colors::PINK;
+// Cells expanded by the search running backwards from the finish.
+pub const BACKWARD_EXPANSION_COLOR: Color = colors::VIOLET;
+// Every other waypoint segment uses this color instead of the path color.
+pub const ALTERNATE_SEGMENT_COLOR: Color = colors::ORANGE;
+// The path towards an unreachable finish, see `closest_approach`.
+pub const PARTIAL_PATH_COLOR: Color = colors::GOLD;
+// Marks the cell where the two sides of a bidirectional search met.
+pub const MEETING_COLOR: Color = colors::MAROON;
+const MEETING_MARKER_RADIUS: f32 = CELL_SIZE / 4.;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Bfs,
+    AStar,
+    BidirectionalBfs,
+}
+
+pub struct SearchResult {
+    pub path: Option<Vec<(usize, usize)>>,
+    pub expanded: Vec<(usize, usize)>,
+    // Only bidirectional searches expand cells from the finish side.
+    pub expanded_backward: Vec<(usize, usize)>,
+    // Set by `search` instead of `path` when the finish can't be reached.
+    pub closest_approach: Option<Vec<(usize, usize)>>,
+    // First cell of the path that was reached from the finish side.
+    pub meeting: Option<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoalMode {
+    // Stop at whichever goal is reached first.
+    Nearest,
+    // Visit the goals in the given order.
+    Ordered,
+}
+
+// A path made of one segment per visited goal. `segments` is `None` if some
+// goal could not be reached.
+pub struct MultiGoalResult {
+    pub segments: Option<Vec<Vec<(usize, usize)>>>,
+    pub expanded: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchStats {
+    pub expanded: usize,
+    pub path_steps: Option<usize>,
+}
+
+impl SearchResult {
+    pub fn stats(&self) -> SearchStats {
+        SearchStats {
+            expanded: self.expanded.len() + self.expanded_backward.len(),
+            path_steps: self.path.as_ref().map(|path| path.len() - 1),
+        }
+    }
+}
+
+pub fn find_and_render_path(
+    algorithm: Algorithm,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    options: &SearchOptions,
+) -> Vec<DrawCommand> {
+    render(search(algorithm, grid, start, finish, options), options)
+}
+
+// Same as `find_path`, but with `options.closest_approach` an unreachable
+// finish also gets the path to the closest cell that can be reached.
+pub fn search(
+    algorithm: Algorithm,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    options: &SearchOptions,
+) -> SearchResult {
+    let mut result = find_path(algorithm, grid, start, finish, options.connectivity);
+    if result.path.is_none() && options.closest_approach {
+        result.closest_approach = Some(closest_approach(grid, start, finish, options.connectivity));
+    }
+    result
+}
+
+pub fn find_and_render_multi_goal_path(
+    algorithm: Algorithm,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    mode: GoalMode,
+    options: &SearchOptions,
+) -> Vec<DrawCommand> {
+    render_multi_goal(
+        find_multi_goal_path(algorithm, grid, start, goals, mode, options.connectivity),
+        options,
+    )
+}
+
+// The nearest goal search is a multi-target BFS regardless of `algorithm`,
+// since a single heuristic can't aim at several goals at once.
+pub fn find_multi_goal_path(
+    algorithm: Algorithm,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    mode: GoalMode,
+    connectivity: Connectivity,
+) -> MultiGoalResult {
+    match mode {
+        GoalMode::Nearest => {
+            let result = bfs_nearest(grid, start, goals, connectivity);
+            MultiGoalResult {
+                segments: result.path.map(|path| vec![path]),
+                expanded: result.expanded,
+            }
+        }
+        GoalMode::Ordered => {
+            let mut segments = Some(vec![]);
+            let mut expanded = vec![];
+            let mut from = start;
+            for &goal in goals {
+                let result = find_path(algorithm, grid, from, goal, connectivity);
+                expanded.extend(result.expanded);
+                expanded.extend(result.expanded_backward);
+                match (segments.as_mut(), result.path) {
+                    (Some(segments), Some(path)) => segments.push(path),
+                    _ => {
+                        segments = None;
+                        break;
+                    }
+                }
+                from = goal;
+            }
+            MultiGoalResult { segments, expanded }
+        }
+    }
+}
+
+pub fn find_path(
+    algorithm: Algorithm,
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> SearchResult {
+    match algorithm {
+        Algorithm::Bfs => bfs(grid, start, finish, connectivity),
+        Algorithm::AStar => astar(grid, start, finish, connectivity),
+        Algorithm::BidirectionalBfs => bidirectional_bfs(grid, start, finish, connectivity),
+    }
+}
+
+// The two sides of a bidirectional search take turns, one cell each, so the
+// animation shows them growing towards each other.
+pub fn render(result: SearchResult, options: &SearchOptions) -> Vec<DrawCommand> {
+    let mut draw_commands = interleave(
+        render_expansion(result.expanded, EXPANSION_COLOR, options),
+        render_expansion(result.expanded_backward, BACKWARD_EXPANSION_COLOR, options),
+    );
+    if let Some(path) = result.path {
+        push_path(&mut draw_commands, path, options);
+    } else if let Some(path) = result.closest_approach {
+        draw_commands.push(DrawCommand::Clear);
+        push_path_lines(&mut draw_commands, &path, PARTIAL_PATH_COLOR);
+    }
+    if let Some((x, y)) = result.meeting {
+        draw_commands.push(DrawCommand::AddShape(Shape::Circle {
+            x,
+            y,
+            r: MEETING_MARKER_RADIUS,
+            color: MEETING_COLOR,
+        }));
+    }
+    draw_commands
+}
+
+// Alternates between the two, the rest of the longer one goes last.
+fn interleave<T>(first: Vec<T>, second: Vec<T>) -> Vec<T> {
+    let mut result = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter();
+    let mut second = second.into_iter();
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => return result,
+            (a, b) => result.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+pub fn render_multi_goal(result: MultiGoalResult, options: &SearchOptions) -> Vec<DrawCommand> {
+    let mut draw_commands = render_expansion(result.expanded, EXPANSION_COLOR, options);
+    if let Some(segments) = result.segments {
+        draw_commands.push(DrawCommand::Clear);
+        for (i, segment) in segments.iter().enumerate() {
+            let color = if i % 2 == 0 {
+                options.path_color
+            } else {
+                ALTERNATE_SEGMENT_COLOR
+            };
+            push_path_lines(&mut draw_commands, segment, color);
+        }
+    }
+    draw_commands
+}
+
+// With `options.heatmap` the expanded cells are tinted by expansion order.
+fn render_expansion(
+    expanded: Vec<(usize, usize)>,
+    color: Color,
+    options: &SearchOptions,
+) -> Vec<DrawCommand> {
+    let last = expanded.len().saturating_sub(1).max(1) as f32;
+    expanded
+        .into_iter()
+        .enumerate()
+        .map(|(i, (x, y))| {
+            DrawCommand::AddShape(Shape::Square {
+                x,
+                y,
+                color,
+                intensity: if options.heatmap { Some(i as f32 / last) } else { None },
+            })
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn bfs(
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> SearchResult {
+    bfs_nearest(grid, start, &[finish], connectivity)
+}
+
+// BFS that stops at the first of `goals` it reaches.
+pub fn bfs_nearest(
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    connectivity: Connectivity,
+) -> SearchResult {
+    let mut came_from = Grid::<Option<(usize, usize)>>::new(grid.width(), grid.height());
+    let mut expanded = vec![];
+    let mut queue = VecDeque::new();
+
+    came_from[start] = Some(start);
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        expanded.push((x, y));
+        if goals.contains(&(x, y)) {
+            return SearchResult {
+                path: Some(reconstruct_path(&came_from, start, (x, y))),
+                expanded,
+                expanded_backward: vec![],
+                closest_approach: None,
+                meeting: None,
+            };
+        }
+        for (nx, ny) in grid.moves(x, y, connectivity) {
+            if came_from[(nx, ny)].is_none() {
+                came_from[(nx, ny)] = Some((x, y));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    SearchResult {
+        path: None,
+        expanded,
+        expanded_backward: vec![],
+        closest_approach: None,
+        meeting: None,
+    }
+}
+
+pub fn astar(
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> SearchResult {
+    let heuristic = |cell| estimate_distance(cell, finish, connectivity);
+
+    let mut came_from = Grid::<Option<(usize, usize)>>::new(grid.width(), grid.height());
+    let mut cost = Grid::<Option<usize>>::new(grid.width(), grid.height());
+    let mut closed = Grid::<bool>::new(grid.width(), grid.height());
+    let mut expanded = vec![];
+    let mut open = BinaryHeap::new();
+
+    came_from[start] = Some(start);
+    cost[start] = Some(0);
+    open.push(Reverse((heuristic(start), 0, start)));
+
+    while let Some(Reverse((_, g, (x, y)))) = open.pop() {
+        if closed[(x, y)] {
+            continue;
+        }
+        closed[(x, y)] = true;
+        expanded.push((x, y));
+        if (x, y) == finish {
+            return SearchResult {
+                path: Some(reconstruct_path(&came_from, start, finish)),
+                expanded,
+                expanded_backward: vec![],
+                closest_approach: None,
+                meeting: None,
+            };
+        }
+        for (nx, ny) in grid.moves(x, y, connectivity) {
+            let new_cost = g + 1;
+            if cost[(nx, ny)].map_or(true, |old_cost| new_cost < old_cost) {
+                cost[(nx, ny)] = Some(new_cost);
+                came_from[(nx, ny)] = Some((x, y));
+                open.push(Reverse((new_cost + heuristic((nx, ny)), new_cost, (nx, ny))));
+            }
+        }
+    }
+
+    SearchResult {
+        path: None,
+        expanded,
+        expanded_backward: vec![],
+        closest_approach: None,
+        meeting: None,
+    }
+}
+
+// Grows one BFS from each end, a whole layer at a time from the smaller
+// frontier. The first layer that touches the other side yields the meeting
+// cell with the shortest combined distance.
+pub fn bidirectional_bfs(
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> SearchResult {
+    if start == finish {
+        return SearchResult {
+            path: Some(vec![start]),
+            expanded: vec![start],
+            expanded_backward: vec![],
+            closest_approach: None,
+            meeting: None,
+        };
+    }
+
+    let mut came_from = [
+        Grid::<Option<(usize, usize)>>::new(grid.width(), grid.height()),
+        Grid::<Option<(usize, usize)>>::new(grid.width(), grid.height()),
+    ];
+    let mut distance = [
+        Grid::<Option<usize>>::new(grid.width(), grid.height()),
+        Grid::<Option<usize>>::new(grid.width(), grid.height()),
+    ];
+    let mut frontier = [vec![start], vec![finish]];
+    let mut expanded = [vec![], vec![]];
+    for (side, &root) in [start, finish].iter().enumerate() {
+        came_from[side][root] = Some(root);
+        distance[side][root] = Some(0);
+    }
+
+    while !frontier[0].is_empty() && !frontier[1].is_empty() {
+        let side = if frontier[0].len() <= frontier[1].len() { 0 } else { 1 };
+        let other = 1 - side;
+
+        // (total distance, cell on this side, cell on the other side)
+        let mut meeting: Option<(usize, (usize, usize), (usize, usize))> = None;
+        let mut next_frontier = vec![];
+        for (x, y) in std::mem::replace(&mut frontier[side], vec![]) {
+            expanded[side].push((x, y));
+            let d = distance[side][(x, y)].expect("frontier cell has no distance");
+            for (nx, ny) in grid.moves(x, y, connectivity) {
+                if let Some(other_d) = distance[other][(nx, ny)] {
+                    let total = d + 1 + other_d;
+                    if meeting.map_or(true, |(best, _, _)| total < best) {
+                        meeting = Some((total, (x, y), (nx, ny)));
+                    }
+                }
+                if came_from[side][(nx, ny)].is_none() {
+                    came_from[side][(nx, ny)] = Some((x, y));
+                    distance[side][(nx, ny)] = Some(d + 1);
+                    next_frontier.push((nx, ny));
+                }
+            }
+        }
+        frontier[side] = next_frontier;
+
+        if let Some((_, near, far)) = meeting {
+            let [forward, backward] = expanded;
+            let (from_start, from_finish) = if side == 0 { (near, far) } else { (far, near) };
+            let mut path = reconstruct_path(&came_from[0], start, from_start);
+            let mut tail = reconstruct_path(&came_from[1], finish, from_finish);
+            tail.reverse();
+            path.extend(tail);
+            return SearchResult {
+                path: Some(path),
+                expanded: forward,
+                expanded_backward: backward,
+                closest_approach: None,
+                meeting: Some(from_finish),
+            };
+        }
+    }
+
+    let [forward, backward] = expanded;
+    SearchResult {
+        path: None,
+        expanded: forward,
+        expanded_backward: backward,
+        closest_approach: None,
+        meeting: None,
+    }
+}
+
+// Number of steps from `start` to every reachable cell, `None` elsewhere.
+pub fn distance_map(
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    connectivity: Connectivity,
+) -> Grid<Option<usize>> {
+    let mut distance = Grid::<Option<usize>>::new(grid.width(), grid.height());
+    let mut queue = VecDeque::new();
+
+    distance[start] = Some(0);
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        let d = distance[(x, y)].expect("queued cell has no distance");
+        for (nx, ny) in grid.moves(x, y, connectivity) {
+            if distance[(nx, ny)].is_none() {
+                distance[(nx, ny)] = Some(d + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    distance
+}
+
+// Shortest path from `start` to the reachable cell closest to `finish` by
+// `estimate_distance`, which is the finish itself if it can be reached. Ties
+// go to the cell with the shorter path.
+pub fn closest_approach(
+    grid: &Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> Vec<(usize, usize)> {
+    let mut came_from = Grid::<Option<(usize, usize)>>::new(grid.width(), grid.height());
+    let mut queue = VecDeque::new();
+    let mut closest = (estimate_distance(start, finish, connectivity), start);
+
+    came_from[start] = Some(start);
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = estimate_distance((x, y), finish, connectivity);
+        if distance < closest.0 {
+            closest = (distance, (x, y));
+        }
+        for (nx, ny) in grid.moves(x, y, connectivity) {
+            if came_from[(nx, ny)].is_none() {
+                came_from[(nx, ny)] = Some((x, y));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    reconstruct_path(&came_from, start, closest.1)
+}
+
+// Number of moves between the cells on an empty grid, which never
+// overestimates the real distance.
+fn estimate_distance(
+    (x, y): (usize, usize),
+    finish: (usize, usize),
+    connectivity: Connectivity,
+) -> usize {
+    match connectivity {
+        Connectivity::Four => Grid::manhattan((x, y), finish),
+        Connectivity::Eight | Connectivity::EightNoCornerCutting => {
+            let dx = (x as i64 - finish.0 as i64).abs() as usize;
+            let dy = (y as i64 - finish.1 as i64).abs() as usize;
+            dx.max(dy)
+        }
+    }
+}
+
+fn reconstruct_path(
+    came_from: &Grid<Option<(usize, usize)>>,
+    start: (usize, usize),
+    finish: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut path = vec![finish];
+    while *path.last().unwrap() != start {
+        let (x, y) = *path.last().unwrap();
+        path.push(came_from[(x, y)].expect("cell on the path has no predecessor"));
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfind::validate_path;
+
+    // Reassembles the path drawn after the last `Clear`, one cell per line
+    // end, along with the colors of its lines.
+    fn drawn_path(draw_commands: &[DrawCommand]) -> (Vec<(usize, usize)>, Vec<Color>) {
+        let clear = draw_commands
+            .iter()
+            .rposition(|command| matches!(command, DrawCommand::Clear))
+            .expect("no path was drawn");
+        let mut cells = vec![];
+        let mut colors = vec![];
+        for command in &draw_commands[clear + 1..] {
+            match command {
+                DrawCommand::AddShape(Shape::Line { from, to, color, .. }) => {
+                    if cells.is_empty() {
+                        cells.push(*from);
+                    }
+                    assert_eq!(cells.last(), Some(from), "path lines are not connected");
+                    cells.push(*to);
+                    colors.push(*color);
+                }
+                other => panic!("unexpected command after the path: {:?}", other),
+            }
+        }
+        (cells, colors)
+    }
+
+    fn expanded_squares(draw_commands: &[DrawCommand]) -> Vec<((usize, usize), Color)> {
+        draw_commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::AddShape(Shape::Square { x, y, color, .. }) => Some(((*x, *y), *color)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn render_draws_expansion_then_one_path() {
+        let mut grid = Grid::new(4, 3);
+        grid.set(1, 0, true);
+        grid.set(1, 1, true);
+        let options = SearchOptions::default();
+        for &algorithm in &[Algorithm::Bfs, Algorithm::AStar] {
+            let result = find_path(algorithm, &grid, (0, 0), (3, 0), options.connectivity);
+            let expanded = result.expanded.clone();
+            let path = result.path.clone().unwrap();
+            let draw_commands = render(result, &options);
+
+            let clears = draw_commands
+                .iter()
+                .filter(|command| matches!(command, DrawCommand::Clear))
+                .count();
+            assert_eq!(clears, 1, "{:?}", algorithm);
+            let squares = expanded_squares(&draw_commands);
+            assert_eq!(
+                squares,
+                expanded.iter().map(|&cell| (cell, EXPANSION_COLOR)).collect::<Vec<_>>()
+            );
+
+            let (cells, colors) = drawn_path(&draw_commands);
+            assert_eq!(cells, path);
+            assert!(colors.iter().all(|&color| color == options.path_color));
+        }
+    }
+
+    #[test]
+    fn bfs_and_astar_find_the_detour() {
+        let mut grid = Grid::new(5, 5);
+        for y in 0..4 {
+            grid.set(2, y, true);
+        }
+        let bfs = find_path(Algorithm::Bfs, &grid, (0, 0), (4, 0), Connectivity::Four);
+        let astar = find_path(Algorithm::AStar, &grid, (0, 0), (4, 0), Connectivity::Four);
+        assert_eq!(bfs.stats().path_steps, Some(12));
+        assert_eq!(astar.stats().path_steps, Some(12));
+    }
+
+    #[test]
+    fn unreachable_finish_draws_no_path() {
+        let mut grid = Grid::new(3, 1);
+        grid.set(1, 0, true);
+        let options = SearchOptions::default();
+        let draw_commands = find_and_render_path(Algorithm::Bfs, &grid, (0, 0), (2, 0), &options);
+        assert!(draw_commands
+            .iter()
+            .all(|command| matches!(command, DrawCommand::AddShape(Shape::Square { .. }))));
+        assert_eq!(expanded_squares(&draw_commands), vec![((0, 0), EXPANSION_COLOR)]);
+    }
+
+    #[test]
+    fn diagonal_path_across_an_open_grid_is_shorter() {
+        let grid = Grid::new(5, 5);
+        for &algorithm in &[Algorithm::Bfs, Algorithm::AStar, Algorithm::BidirectionalBfs] {
+            let steps = |connectivity| {
+                find_path(algorithm, &grid, (0, 0), (4, 4), connectivity)
+                    .stats()
+                    .path_steps
+            };
+            assert_eq!(steps(Connectivity::Four), Some(8), "{:?}", algorithm);
+            assert_eq!(steps(Connectivity::Eight), Some(4), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn diagonal_path_is_valid_only_with_eight_connectivity() {
+        let grid = Grid::new(5, 5);
+        let path = find_path(Algorithm::Bfs, &grid, (0, 0), (4, 4), Connectivity::Eight)
+            .path
+            .unwrap();
+        assert_eq!(validate_path(&path, &grid, (0, 0), (4, 4), Connectivity::Eight), Ok(()));
+        assert!(validate_path(&path, &grid, (0, 0), (4, 4), Connectivity::Four).is_err());
+    }
+
+    // Two walls touching at a corner, with the start and finish on the
+    // diagonal through it.
+    fn squeezed_diagonal() -> Grid<bool> {
+        let mut grid = Grid::new(2, 2);
+        grid.set(1, 0, true);
+        grid.set(0, 1, true);
+        grid
+    }
+
+    #[test]
+    fn diagonal_between_two_wall_corners_is_rejected_without_corner_cutting() {
+        let grid = squeezed_diagonal();
+        let path = vec![(0, 0), (1, 1)];
+        assert_eq!(validate_path(&path, &grid, (0, 0), (1, 1), Connectivity::Eight), Ok(()));
+        let error = validate_path(&path, &grid, (0, 0), (1, 1), Connectivity::EightNoCornerCutting).unwrap_err();
+        assert!(error.contains("cuts the wall corner"), "{}", error);
+    }
+
+    #[test]
+    fn searches_do_not_cut_wall_corners_when_told_not_to() {
+        let grid = squeezed_diagonal();
+        for &algorithm in &[Algorithm::Bfs, Algorithm::AStar, Algorithm::BidirectionalBfs] {
+            let steps = |connectivity| {
+                find_path(algorithm, &grid, (0, 0), (1, 1), connectivity)
+                    .stats()
+                    .path_steps
+            };
+            assert_eq!(steps(Connectivity::Eight), Some(1), "{:?}", algorithm);
+            assert_eq!(steps(Connectivity::EightNoCornerCutting), None, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn diagonal_past_a_single_wall_is_still_allowed() {
+        let mut grid = Grid::new(2, 2);
+        grid.set(1, 0, true);
+        let path = find_path(Algorithm::Bfs, &grid, (0, 0), (1, 1), Connectivity::EightNoCornerCutting)
+            .path
+            .unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 1)]);
+        assert_eq!(
+            validate_path(&path, &grid, (0, 0), (1, 1), Connectivity::EightNoCornerCutting),
+            Ok(())
+        );
+    }
+
+    fn row(width: usize) -> Grid<bool> {
+        Grid::new(width, 1)
+    }
+
+    #[test]
+    fn nearest_goal_mode_stops_at_the_closest_goal() {
+        let result = find_multi_goal_path(
+            Algorithm::AStar,
+            &row(6),
+            (1, 0),
+            &[(5, 0), (0, 0)],
+            GoalMode::Nearest,
+            Connectivity::Four,
+        );
+        assert_eq!(result.segments, Some(vec![vec![(1, 0), (0, 0)]]));
+    }
+
+    #[test]
+    fn ordered_mode_visits_goals_in_order() {
+        let result = find_multi_goal_path(
+            Algorithm::Bfs,
+            &row(6),
+            (1, 0),
+            &[(5, 0), (0, 0)],
+            GoalMode::Ordered,
+            Connectivity::Four,
+        );
+        let segments = result.segments.unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0], (1..6).map(|x| (x, 0)).collect::<Vec<_>>());
+        assert_eq!(segments[1], (0..6).rev().map(|x| (x, 0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ordered_mode_fails_on_an_unreachable_goal() {
+        let mut grid = row(6);
+        grid.set(3, 0, true);
+        for &mode in &[GoalMode::Ordered, GoalMode::Nearest] {
+            let result =
+                find_multi_goal_path(Algorithm::Bfs, &grid, (1, 0), &[(5, 0)], mode, Connectivity::Four);
+            assert!(result.segments.is_none(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn multi_goal_segments_alternate_colors() {
+        let options = SearchOptions::default();
+        let draw_commands = find_and_render_multi_goal_path(
+            Algorithm::Bfs,
+            &row(4),
+            (1, 0),
+            &[(3, 0), (0, 0)],
+            GoalMode::Ordered,
+            &options,
+        );
+        let (cells, colors) = drawn_path(&draw_commands);
+        assert_eq!(cells, vec![(1, 0), (2, 0), (3, 0), (2, 0), (1, 0), (0, 0)]);
+        let path = options.path_color;
+        let alternate = ALTERNATE_SEGMENT_COLOR;
+        assert_eq!(colors, vec![path, path, alternate, alternate, alternate]);
+    }
+
+    #[test]
+    fn bidirectional_bfs_matches_bfs_with_fewer_expansions() {
+        let grid = Grid::new(31, 31);
+        let (start, finish) = ((0, 15), (30, 15));
+        let bfs = bfs(&grid, start, finish, Connectivity::Four);
+        let bidirectional = bidirectional_bfs(&grid, start, finish, Connectivity::Four);
+
+        assert_eq!(bidirectional.stats().path_steps, Some(30));
+        assert_eq!(bidirectional.stats().path_steps, bfs.stats().path_steps);
+        assert!(bidirectional.stats().expanded <= bfs.stats().expanded);
+        assert!(!bidirectional.expanded_backward.is_empty());
+
+        let path = bidirectional.path.unwrap();
+        assert_eq!((path.first(), path.last()), (Some(&start), Some(&finish)));
+        assert_eq!(validate_path(&path, &grid, start, finish, Connectivity::Four), Ok(()));
+        assert!(path.contains(&bidirectional.meeting.unwrap()));
+    }
+
+    #[test]
+    fn bidirectional_bfs_finds_shortest_paths_through_noise() {
+        for seed in 0..20 {
+            let mut grid = Grid::new(12, 9);
+            grid.random_fill(0.3, seed);
+            let (start, finish) = ((0, 0), (11, 8));
+            grid.set(start.0, start.1, false);
+            grid.set(finish.0, finish.1, false);
+            for &connectivity in &[Connectivity::Four, Connectivity::Eight] {
+                let expected = bfs(&grid, start, finish, connectivity).stats().path_steps;
+                let actual = bidirectional_bfs(&grid, start, finish, connectivity).stats().path_steps;
+                assert_eq!(actual, expected, "seed {} with {:?}", seed, connectivity);
+            }
+        }
+    }
+
+    #[test]
+    fn bidirectional_rendering_colors_the_two_frontiers() {
+        let result = bidirectional_bfs(&row(6), (0, 0), (5, 0), Connectivity::Four);
+        let forward = result.expanded.len();
+        let backward = result.expanded_backward.len();
+        let squares = expanded_squares(&render(result, &SearchOptions::default()));
+
+        let count = |color| squares.iter().filter(|&&(_, c)| c == color).count();
+        assert_eq!(count(EXPANSION_COLOR), forward);
+        assert_eq!(count(BACKWARD_EXPANSION_COLOR), backward);
+        assert_eq!(squares[0], ((0, 0), EXPANSION_COLOR));
+        assert_eq!(squares[1], ((5, 0), BACKWARD_EXPANSION_COLOR));
+    }
+
+    #[test]
+    fn bidirectional_expansions_alternate_sides() {
+        let result = bidirectional_bfs(&Grid::new(7, 7), (0, 3), (6, 3), Connectivity::Four);
+        let squares = expanded_squares(&render(result, &SearchOptions::default()));
+        assert!(squares.len() >= 8);
+        for (i, &(_, color)) in squares[..8].iter().enumerate() {
+            let expected = if i % 2 == 0 { EXPANSION_COLOR } else { BACKWARD_EXPANSION_COLOR };
+            assert_eq!(color, expected, "square {}", i);
+        }
+    }
+
+    #[test]
+    fn interleave_alternates_then_appends_the_rest() {
+        assert_eq!(interleave(vec![1, 3, 5, 7], vec![2, 4]), vec![1, 2, 3, 4, 5, 7]);
+        assert_eq!(interleave(vec![1], vec![2, 4, 6]), vec![1, 2, 4, 6]);
+        assert_eq!(interleave(Vec::<i32>::new(), vec![]), vec![]);
+    }
+
+    #[test]
+    fn meeting_cell_is_marked_on_top_of_the_path() {
+        let result = bidirectional_bfs(&row(6), (0, 0), (5, 0), Connectivity::Four);
+        let meeting = result.meeting.expect("the two sides should have met");
+        assert!(result.path.as_ref().unwrap().contains(&meeting));
+
+        let draw_commands = render(result, &SearchOptions::default());
+        match draw_commands.last() {
+            Some(DrawCommand::AddShape(Shape::Circle { x, y, color, .. })) => {
+                assert_eq!((*x, *y), meeting);
+                assert_eq!(*color, MEETING_COLOR);
+            }
+            other => panic!("expected the meeting marker last, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn one_sided_searches_have_no_meeting_marker() {
+        let result = find_path(Algorithm::Bfs, &row(6), (0, 0), (5, 0), Connectivity::Four);
+        assert_eq!(result.meeting, None);
+        let draw_commands = render(result, &SearchOptions::default());
+        assert!(!draw_commands
+            .iter()
+            .any(|command| matches!(command, DrawCommand::AddShape(Shape::Circle { .. }))));
+    }
+
+    #[test]
+    fn distance_map_of_an_open_grid_is_manhattan_distance() {
+        let grid = Grid::new(5, 4);
+        let start = (1, 2);
+        let distances = distance_map(&grid, start, Connectivity::Four);
+        for (x, y) in grid.keys() {
+            assert_eq!(distances.get(x, y), Some(Grid::manhattan(start, (x, y))), "cell ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn distance_map_leaves_walls_and_unreachable_cells_empty() {
+        let mut grid = Grid::new(4, 2);
+        grid.set(1, 0, true);
+        grid.set(1, 1, true);
+        let distances = distance_map(&grid, (0, 0), Connectivity::Four);
+        assert_eq!(distances.get(0, 1), Some(1));
+        assert!((1..4).all(|x| distances.get(x, 0).is_none() && distances.get(x, 1).is_none()));
+    }
+
+    // A 5x5 grid with the finish walled off in the top right corner. The
+    // reachable cell closest to it is (4, 2).
+    fn enclosed_finish() -> Grid<bool> {
+        let mut grid = Grid::new(5, 5);
+        for &(x, y) in &[(2, 4), (3, 4), (3, 3), (4, 3)] {
+            grid.set(x, y, true);
+        }
+        grid
+    }
+
+    #[test]
+    fn enclosed_finish_falls_back_to_the_closest_approach() {
+        let grid = enclosed_finish();
+        let options = SearchOptions {
+            closest_approach: true,
+            ..SearchOptions::default()
+        };
+        for &algorithm in &[Algorithm::Bfs, Algorithm::AStar, Algorithm::BidirectionalBfs] {
+            let result = search(algorithm, &grid, (0, 0), (4, 4), &options);
+            assert_eq!(result.path, None, "{:?}", algorithm);
+            let approach = result.closest_approach.clone().expect("no closest approach");
+            assert_eq!(approach.len(), 7, "{:?}", algorithm);
+            assert_eq!(validate_path(&approach, &grid, (0, 0), (4, 2), Connectivity::Four), Ok(()));
+
+            let (cells, colors) = drawn_path(&render(result, &options));
+            assert_eq!(cells, approach);
+            assert!(colors.iter().all(|&color| color == PARTIAL_PATH_COLOR));
+        }
+    }
+
+    #[test]
+    fn closest_approach_is_off_by_default() {
+        let result = search(Algorithm::Bfs, &enclosed_finish(), (0, 0), (4, 4), &SearchOptions::default());
+        assert_eq!((result.path, result.closest_approach), (None, None));
+    }
+
+    #[test]
+    fn closest_approach_to_a_reachable_finish_is_the_shortest_path() {
+        let mut grid = enclosed_finish();
+        grid.set(4, 3, false);
+        let approach = closest_approach(&grid, (0, 0), (4, 4), Connectivity::Four);
+        let shortest = find_path(Algorithm::Bfs, &grid, (0, 0), (4, 4), Connectivity::Four);
+        assert_eq!(approach.last(), Some(&(4, 4)));
+        assert_eq!(Some(approach.len() - 1), shortest.stats().path_steps);
+    }
+}
@@ -0,0 +1,186 @@
+use super::{find_and_render_path, SearchOptions, UserSearch};
+use crate::grid::Grid;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub const DEFAULT_CACHE_CAPACITY: usize = 16;
+
+#[derive(Clone, PartialEq, Eq)]
+struct CacheKey {
+    // Hash of the code together with every option that changes the result.
+    code_hash: u64,
+    grid: Grid<bool>,
+    start: (usize, usize),
+    finish: (usize, usize),
+}
+
+impl CacheKey {
+    fn new(
+        code: &str,
+        grid: &Grid<bool>,
+        start: (usize, usize),
+        finish: (usize, usize),
+        options: &SearchOptions,
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        options.entry_point.hash(&mut hasher);
+        options.connectivity.hash(&mut hasher);
+        options.convention.hash(&mut hasher);
+        options.grid_as_list.hash(&mut hasher);
+        options.heatmap.hash(&mut hasher);
+        options.weights.hash(&mut hasher);
+        options.color_by_weight.hash(&mut hasher);
+        let gradient = options.path_gradient.iter().flat_map(|&(from, to)| vec![from, to]);
+        for color in Some(options.path_color).into_iter().chain(gradient) {
+            for component in &[color.r, color.g, color.b, color.a] {
+                component.to_bits().hash(&mut hasher);
+            }
+        }
+        Self {
+            code_hash: hasher.finish(),
+            grid: grid.clone(),
+            start,
+            finish,
+        }
+    }
+}
+
+// Remembers the last few successful `find_and_render_path` results, so running
+// unchanged code on an unchanged maze does not execute the Python again. Any
+// change to the code, grid, endpoints or options makes a new key, so nothing
+// ever has to be invalidated explicitly. Errors are not cached since a
+// cancelled run would otherwise stick.
+pub struct SearchCache {
+    capacity: usize,
+    // Most recently used entries are at the front.
+    entries: VecDeque<(CacheKey, UserSearch)>,
+    hits: usize,
+    misses: usize,
+}
+
+impl Default for SearchCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl SearchCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "search cache capacity must be positive");
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn find_and_render_path(
+        &mut self,
+        code: &str,
+        grid: &Grid<bool>,
+        start: (usize, usize),
+        finish: (usize, usize),
+        options: &SearchOptions,
+    ) -> Result<UserSearch, String> {
+        let key = CacheKey::new(code, grid, start, finish, options);
+        if let Some(index) = self.entries.iter().position(|(cached, _)| *cached == key) {
+            self.hits += 1;
+            let entry = self.entries.remove(index).unwrap();
+            let search = entry.1.clone();
+            self.entries.push_front(entry);
+            return Ok(search);
+        }
+
+        self.misses += 1;
+        let search = find_and_render_path(code, grid, start, finish, options)?;
+        if self.entries.len() == self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((key, search.clone()));
+        Ok(search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "def find_path(grid, start, finish):\n    return [start, (0, 1), finish]\n";
+
+    fn search(cache: &mut SearchCache, code: &str, grid: &Grid<bool>) -> Result<UserSearch, String> {
+        cache.find_and_render_path(code, grid, (0, 0), (2, 0), &SearchOptions::default())
+    }
+
+    #[test]
+    fn identical_calls_hit_and_a_changed_grid_misses() {
+        let mut cache = SearchCache::default();
+        let grid = Grid::new(3, 2);
+        search(&mut cache, CODE, &grid).unwrap();
+        search(&mut cache, CODE, &grid).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        let mut changed = grid.clone();
+        changed.set(1, 1, true);
+        search(&mut cache, CODE, &changed).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[test]
+    fn changed_options_miss() {
+        let mut cache = SearchCache::default();
+        let grid = Grid::new(3, 2);
+        search(&mut cache, CODE, &grid).unwrap();
+        let options = SearchOptions {
+            heatmap: true,
+            ..SearchOptions::default()
+        };
+        cache
+            .find_and_render_path(CODE, &grid, (0, 0), (2, 0), &options)
+            .unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let mut cache = SearchCache::new(2);
+        let grids: Vec<_> = (0..3).map(|x| Grid::from_fn(3, 2, |cx, cy| (cx, cy) == (x, 1))).collect();
+        search(&mut cache, CODE, &grids[0]).unwrap();
+        search(&mut cache, CODE, &grids[1]).unwrap();
+        // Touching the first grid makes the second one the oldest.
+        search(&mut cache, CODE, &grids[0]).unwrap();
+        search(&mut cache, CODE, &grids[2]).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+
+        search(&mut cache, CODE, &grids[0]).unwrap();
+        search(&mut cache, CODE, &grids[1]).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (2, 4));
+    }
+
+    #[test]
+    fn errors_are_not_cached() {
+        let mut cache = SearchCache::default();
+        let grid = Grid::new(3, 2);
+        let failing = "def find_path(grid, start, finish):\n    raise ValueError()\n";
+        assert!(search(&mut cache, failing, &grid).is_err());
+        assert!(search(&mut cache, failing, &grid).is_err());
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    }
+}
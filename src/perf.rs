@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Averages frame times over a sliding window of the most recent frames.
+pub struct FrameTimer {
+    window: usize,
+    samples: VecDeque<f32>,
+    total: f32,
+}
+
+impl FrameTimer {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            samples: VecDeque::new(),
+            total: 0.,
+        }
+    }
+
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.max(1);
+        self.trim();
+    }
+
+    pub fn record(&mut self, delta: f32) {
+        self.samples.push_back(delta);
+        self.total += delta;
+        self.trim();
+    }
+
+    pub fn frame_time(&self) -> f32 {
+        if self.samples.is_empty() {
+            0.
+        } else {
+            self.total / self.samples.len() as f32
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        let frame_time = self.frame_time();
+        if frame_time > 0. {
+            1. / frame_time
+        } else {
+            0.
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.samples.len() > self.window {
+            self.total -= self.samples.pop_front().unwrap();
+        }
+    }
+}
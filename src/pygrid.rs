@@ -0,0 +1,161 @@
+use crate::grid::{Connectivity, CoordConvention, Grid};
+
+use py::builtins::PyTypeRef;
+use py::pyobject::{PyClassImpl, PyObjectRef, PyRef, PyResult, PyValue, StaticType};
+use py::VirtualMachine;
+use rustpython_vm as py;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Read-only view of the walls exposed to Python. Cells outside the grid are
+// reported as walls, so bounds checks can be folded into `is_wall`. Cell
+// arguments and results follow the search's coordinate convention.
+#[py::pyclass(module = "pathfind", name = "GridProxy")]
+#[derive(Debug)]
+pub struct PyGridProxy {
+    grid: Rc<Grid<bool>>,
+    connectivity: Connectivity,
+    convention: CoordConvention,
+}
+
+pub type PyGridProxyRef = PyRef<PyGridProxy>;
+
+impl PyValue for PyGridProxy {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+impl PyGridProxy {
+    pub fn new(grid: Rc<Grid<bool>>, connectivity: Connectivity, convention: CoordConvention) -> Self {
+        Self {
+            grid,
+            connectivity,
+            convention,
+        }
+    }
+}
+
+#[py::pyimpl]
+impl PyGridProxy {
+    #[pyproperty]
+    fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    #[pyproperty]
+    fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    #[pymethod]
+    fn in_bounds(&self, a: i64, b: i64) -> bool {
+        let (x, y) = self.convention.apply((a, b));
+        self.grid.are_coordinates_valid(x, y)
+    }
+
+    #[pymethod]
+    fn is_wall(&self, a: i64, b: i64) -> bool {
+        let (x, y) = self.convention.apply((a, b));
+        self.grid.try_get(x, y).unwrap_or(true)
+    }
+
+    #[pymethod]
+    fn neighbors(&self, a: i64, b: i64, vm: &VirtualMachine) -> PyObjectRef {
+        let (x, y) = self.convention.apply((a, b));
+        let cells = if self.grid.are_coordinates_valid(x, y) {
+            self.grid
+                .moves(x as usize, y as usize, self.connectivity)
+                .map(|(nx, ny)| {
+                    let (na, nb) = self.convention.apply((nx, ny));
+                    vm.ctx.new_tuple(vec![vm.ctx.new_int(na), vm.ctx.new_int(nb)])
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        vm.ctx.new_list(cells)
+    }
+}
+
+pub fn make_class(vm: &VirtualMachine) -> PyTypeRef {
+    PyGridProxy::make_class(&vm.ctx)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// An integer per cell that scripts may freely write, e.g. to mark visited
+// cells. It has the size of the maze but is separate from the walls, so
+// writing to it can't open a path. Every search starts with all zeros.
+#[py::pyclass(module = "pathfind", name = "ScratchGrid")]
+#[derive(Debug)]
+pub struct PyScratchGrid {
+    values: RefCell<Grid<i64>>,
+    convention: CoordConvention,
+}
+
+pub type PyScratchGridRef = PyRef<PyScratchGrid>;
+
+impl PyValue for PyScratchGrid {
+    fn class(_vm: &VirtualMachine) -> &PyTypeRef {
+        Self::static_type()
+    }
+}
+
+impl PyScratchGrid {
+    pub fn new(width: usize, height: usize, convention: CoordConvention) -> Self {
+        Self {
+            values: RefCell::new(Grid::new(width, height)),
+            convention,
+        }
+    }
+
+    fn cell(&self, a: i64, b: i64, vm: &VirtualMachine) -> PyResult<(usize, usize)> {
+        let (x, y) = self.convention.apply((a, b));
+        if self.values.borrow().are_coordinates_valid(x, y) {
+            Ok((x as usize, y as usize))
+        } else {
+            Err(vm.new_index_error(format!("Cell {:?} is outside of the grid", (a, b))))
+        }
+    }
+}
+
+#[py::pyimpl]
+impl PyScratchGrid {
+    #[pyproperty]
+    fn width(&self) -> usize {
+        self.values.borrow().width()
+    }
+
+    #[pyproperty]
+    fn height(&self) -> usize {
+        self.values.borrow().height()
+    }
+
+    #[pymethod]
+    fn get(&self, a: i64, b: i64, vm: &VirtualMachine) -> PyResult<i64> {
+        let (x, y) = self.cell(a, b, vm)?;
+        Ok(self.values.borrow().get(x, y))
+    }
+
+    #[pymethod]
+    fn set(&self, a: i64, b: i64, value: i64, vm: &VirtualMachine) -> PyResult<()> {
+        let (x, y) = self.cell(a, b, vm)?;
+        self.values.borrow_mut().set(x, y, value);
+        Ok(())
+    }
+
+    #[pymethod]
+    fn clear(&self) {
+        let mut values = self.values.borrow_mut();
+        let (width, height) = (values.width(), values.height());
+        *values = Grid::new(width, height);
+    }
+}
+
+pub fn make_scratch_class(vm: &VirtualMachine) -> PyTypeRef {
+    PyScratchGrid::make_class(&vm.ctx)
+}
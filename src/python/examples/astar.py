@@ -0,0 +1,38 @@
+from pathfind import PriorityQueue, manhattan
+
+# Cells are (row, col) and the grid is indexed as grid[row][col].
+def find_path(grid, start, finish):
+    rows = len(grid)
+    cols = len(grid[0])
+
+    prev = {start: start}
+    cost = {start: 0}
+    queue = PriorityQueue()
+    queue.push(start, manhattan(start, finish))
+
+    deltas = [
+        (1, 0),
+        (0, 1),
+        (-1, 0),
+        (0, -1),
+    ]
+
+    while queue:
+        row, col = queue.pop()
+        if (row, col) == finish:
+            path = [finish]
+            while path[-1] != start:
+                path.append(prev[path[-1]])
+            return list(reversed(path))
+
+        draw_square((row, col))
+        for (drow, dcol) in deltas:
+            nrow, ncol = (row + drow, col + dcol)
+            if 0 <= nrow < rows and 0 <= ncol < cols and not grid[nrow][ncol]:
+                new_cost = cost[(row, col)] + 1
+                if (nrow, ncol) not in cost or new_cost < cost[(nrow, ncol)]:
+                    cost[(nrow, ncol)] = new_cost
+                    prev[(nrow, ncol)] = (row, col)
+                    queue.push((nrow, ncol), new_cost + manhattan((nrow, ncol), finish))
+
+    return None
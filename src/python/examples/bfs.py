@@ -0,0 +1,39 @@
+from collections import deque
+
+# Cells are (row, col) and the grid is indexed as grid[row][col].
+def find_path(grid, start, finish):
+    if start == finish:
+        return [start]
+
+    rows = len(grid)
+    cols = len(grid[0])
+
+    prev = {start: start}
+    queue = deque([start])
+
+    deltas = [
+        (1, 0),
+        (0, 1),
+        (-1, 0),
+        (0, -1),
+    ]
+
+    while queue:
+        row, col = queue.popleft()
+        for (drow, dcol) in deltas:
+            nrow, ncol = (row + drow, col + dcol)
+            if (
+                0 <= nrow < rows
+                and 0 <= ncol < cols
+                and not grid[nrow][ncol]
+                and (nrow, ncol) not in prev
+            ):
+                prev[(nrow, ncol)] = (row, col)
+                draw_line((row, col), (nrow, ncol))
+
+                if (nrow, ncol) == finish:
+                    return reconstruct_path(prev, start, finish)
+
+                queue.append((nrow, ncol))
+
+    return None
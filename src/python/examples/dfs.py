@@ -0,0 +1,36 @@
+# Cells are (row, col) and the grid is indexed as grid[row][col].
+def find_path(grid, start, finish):
+    rows = len(grid)
+    cols = len(grid[0])
+
+    prev = {start: start}
+    stack = [start]
+
+    deltas = [
+        (1, 0),
+        (0, 1),
+        (-1, 0),
+        (0, -1),
+    ]
+
+    while stack:
+        row, col = stack.pop()
+        if (row, col) == finish:
+            path = [finish]
+            while path[-1] != start:
+                path.append(prev[path[-1]])
+            return list(reversed(path))
+
+        for (drow, dcol) in deltas:
+            nrow, ncol = (row + drow, col + dcol)
+            if (
+                0 <= nrow < rows
+                and 0 <= ncol < cols
+                and not grid[nrow][ncol]
+                and (nrow, ncol) not in prev
+            ):
+                prev[(nrow, ncol)] = (row, col)
+                draw_line((row, col), (nrow, ncol))
+                stack.append((nrow, ncol))
+
+    return None
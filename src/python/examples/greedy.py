@@ -0,0 +1,42 @@
+from pathfind import PriorityQueue, manhattan
+
+# Cells are (row, col) and the grid is indexed as grid[row][col].
+# Greedy best-first search always expands the cell that looks closest to the
+# finish. It is fast, but unlike A* the path it finds is not always shortest.
+def find_path(grid, start, finish):
+    rows = len(grid)
+    cols = len(grid[0])
+
+    prev = {start: start}
+    queue = PriorityQueue()
+    queue.push(start, manhattan(start, finish))
+
+    deltas = [
+        (1, 0),
+        (0, 1),
+        (-1, 0),
+        (0, -1),
+    ]
+
+    while queue:
+        row, col = queue.pop()
+        if (row, col) == finish:
+            path = [finish]
+            while path[-1] != start:
+                path.append(prev[path[-1]])
+            return list(reversed(path))
+
+        draw_square((row, col))
+        for (drow, dcol) in deltas:
+            nrow, ncol = (row + drow, col + dcol)
+            if (
+                0 <= nrow < rows
+                and 0 <= ncol < cols
+                and not grid[nrow][ncol]
+                and (nrow, ncol) not in prev
+            ):
+                prev[(nrow, ncol)] = (row, col)
+                draw_line((row, col), (nrow, ncol))
+                queue.push((nrow, ncol), manhattan((nrow, ncol), finish))
+
+    return None
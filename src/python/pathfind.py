@@ -0,0 +1,44 @@
+import heapq
+import math
+
+
+# `manhattan` is provided by the host, see `register_helpers_module`.
+
+
+def euclidean(a, b):
+    return math.hypot(a[0] - b[0], a[1] - b[1])
+
+
+class PriorityQueue:
+    def __init__(self):
+        self._heap = []
+        self._counter = 0
+
+    def push(self, item, priority):
+        # The counter breaks ties so that items never get compared directly.
+        heapq.heappush(self._heap, (priority, self._counter, item))
+        self._counter += 1
+
+    def pop(self):
+        return heapq.heappop(self._heap)[2]
+
+    def __len__(self):
+        return len(self._heap)
+
+
+def reconstruct_path(came_from, start, finish):
+    # `came_from` maps every reached cell to the cell it was reached from.
+    # Returns the cells from `start` to `finish`, both included, or None if
+    # `finish` was never reached or its chain of predecessors doesn't lead
+    # back to `start`. The entry of `start` itself is never looked at, so it
+    # can map to itself, to None, or be missing.
+    path = [finish]
+    seen = {finish}
+    while path[-1] != start:
+        prev = came_from.get(path[-1])
+        if prev is None or prev in seen:
+            return None
+        path.append(prev)
+        seen.add(prev)
+    path.reverse()
+    return path
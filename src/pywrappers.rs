@@ -1,7 +1,10 @@
+use py::builtins::dict::PyDictRef;
 use py::builtins::tuple::PyTupleRef;
 use py::pyobject::{BorrowValue, PyIterable, PyObjectRef, PyResult, TryFromObject};
 use rustpython_vm as py;
 
+use quad_gl::Color;
+
 ////////////////////////////////////////////////////////////////////////////////
 
 pub struct PyVecWrapper<T: TryFromObject>(pub Vec<T>);
@@ -33,3 +36,43 @@ impl<T: TryFromObject, U: TryFromObject> TryFromObject for PyTuple2Wrapper<T, U>
         }
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Keeps the entries in the dict's iteration (insertion) order.
+pub struct PyDictWrapper<K: TryFromObject, V: TryFromObject>(pub Vec<(K, V)>);
+
+impl<K: TryFromObject, V: TryFromObject> TryFromObject for PyDictWrapper<K, V> {
+    fn try_from_object(vm: &py::VirtualMachine, obj: PyObjectRef) -> PyResult<Self> {
+        let dict = PyDictRef::try_from_object(vm, obj)?;
+        let mut entries = vec![];
+        for (key, value) in &dict {
+            entries.push((K::try_from_object(vm, key)?, V::try_from_object(vm, value)?));
+        }
+        Ok(Self(entries))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// An `(r, g, b)` or `(r, g, b, a)` tuple with components in [0, 1].
+pub struct PyColorWrapper(pub Color);
+
+impl TryFromObject for PyColorWrapper {
+    fn try_from_object(vm: &py::VirtualMachine, obj: PyObjectRef) -> PyResult<Self> {
+        let PyVecWrapper(components) = PyVecWrapper::<f64>::try_from_object(vm, obj)?;
+        if components.len() != 3 && components.len() != 4 {
+            return Err(vm.new_type_error("Expected color to be a tuple of 3 or 4 numbers".to_owned()));
+        }
+        if components.iter().any(|c| !(0. ..=1.).contains(c)) {
+            return Err(vm.new_value_error("Expected color components to be between 0 and 1".to_owned()));
+        }
+        let alpha = components.get(3).copied().unwrap_or(1.);
+        Ok(Self(Color::new(
+            components[0] as f32,
+            components[1] as f32,
+            components[2] as f32,
+            alpha as f32,
+        )))
+    }
+}
@@ -1,6 +1,8 @@
+pub use macroquad::prelude::{Image, Rect, Texture2D};
 pub use quad_gl::{colors, Color};
 
 use macroquad::prelude as mq;
+use quad_gl::{DrawMode, Vertex};
 
 use std::cell::RefCell;
 use std::collections::HashSet;
@@ -8,29 +10,301 @@ use std::rc::Rc;
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct DrawContext {}
+pub trait DrawContext {
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color);
+    fn draw_circle(&mut self, x: f32, y: f32, r: f32, color: Color);
+    // A ring of the given thickness whose outer edge has radius `r`.
+    fn draw_circle_outline(&mut self, x: f32, y: f32, r: f32, thickness: f32, color: Color);
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color);
+    fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color);
+    // Stretches the texture over the rectangle with its top row at `y + h`.
+    fn draw_texture(&mut self, texture: Texture2D, x: f32, y: f32, w: f32, h: f32);
 
-impl DrawContext {
-    pub fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+    // Returns the width and height `draw_text` would cover, in the same units.
+    fn measure_text(&mut self, text: &str, font_size: f32) -> (f32, f32);
+
+    fn draw_rectangles(&mut self, rects: &[Rect], color: Color) {
+        for rect in rects {
+            self.draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The most vertices and indices a single quad-gl draw call holds. Geometry
+// beyond them doesn't fit, so every batch has to stay within both. quad-gl
+// doesn't export these, they are the values of the vendored version.
+pub const QUAD_GL_MAX_VERTICES: usize = 10000;
+pub const QUAD_GL_MAX_INDICES: usize = 5000;
+
+const VERTICES_PER_RECT: usize = 4;
+const INDICES_PER_RECT: usize = 6;
+// The index limit is the tighter one, 833 rectangles.
+pub const MAX_RECTS_PER_BATCH: usize = QUAD_GL_MAX_INDICES / INDICES_PER_RECT;
+
+// Appends two triangles per rectangle. Indices count from the first vertex
+// added, so `vertices` should start out empty.
+pub fn push_rect_geometry(
+    rects: &[Rect],
+    color: Color,
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u16>,
+) {
+    for (i, rect) in rects.iter().enumerate() {
+        let base = (VERTICES_PER_RECT * i) as u16;
+        vertices.push(Vertex::new(rect.x, rect.y, 0., 0., 0., color));
+        vertices.push(Vertex::new(rect.x + rect.w, rect.y, 0., 1., 0., color));
+        vertices.push(Vertex::new(rect.x + rect.w, rect.y + rect.h, 0., 1., 1., color));
+        vertices.push(Vertex::new(rect.x, rect.y + rect.h, 0., 0., 1., color));
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+pub struct MacroquadDrawContext {
+    camera: mq::Camera2D,
+    viewport: Viewport,
+    // Camera for drawing text in pixels of the target, the default camera
+    // (the screen) when `None`.
+    text_camera: Option<mq::Camera2D>,
+}
+
+impl DrawContext for MacroquadDrawContext {
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
         mq::draw_rectangle(x, y, w, h, color);
     }
 
-    pub fn draw_circle(&mut self, x: f32, y: f32, r: f32, color: Color) {
+    // Submits all rectangles as a few large geometry batches instead of one
+    // `draw_rectangle` call per rectangle.
+    fn draw_rectangles(&mut self, rects: &[Rect], color: Color) {
+        let gl = unsafe { mq::get_internal_gl() }.quad_gl;
+        gl.texture(None);
+        gl.draw_mode(DrawMode::Triangles);
+
+        let mut vertices = Vec::with_capacity(VERTICES_PER_RECT * MAX_RECTS_PER_BATCH);
+        let mut indices = Vec::with_capacity(INDICES_PER_RECT * MAX_RECTS_PER_BATCH);
+        for batch in rects.chunks(MAX_RECTS_PER_BATCH) {
+            vertices.clear();
+            indices.clear();
+            push_rect_geometry(batch, color, &mut vertices, &mut indices);
+            gl.geometry(&vertices, &indices);
+        }
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, r: f32, color: Color) {
         mq::draw_circle(x, y, r, color);
     }
 
-    pub fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
-        mq::draw_text(text, x, y, font_size, color);
+    fn draw_circle_outline(&mut self, x: f32, y: f32, r: f32, thickness: f32, color: Color) {
+        mq::draw_circle_lines(x, y, r, thickness, color);
     }
 
-    pub fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) {
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        // The scene camera flips the y axis, which would render glyphs upside
+        // down, so text is drawn in pixels at the projected position.
+        let (screen_x, screen_y) = self.viewport.scene_to_screen(x, y);
+        match self.text_camera {
+            Some(camera) => mq::set_camera(camera),
+            None => mq::set_default_camera(),
+        }
+        mq::draw_text(text, screen_x, screen_y, font_size * self.viewport.scale, color);
+        mq::set_camera(self.camera);
+    }
+
+    fn measure_text(&mut self, text: &str, font_size: f32) -> (f32, f32) {
+        // macroquad measures in pixels, so measure at the projected font size
+        // and scale the result back into scene units.
+        let scale = self.viewport.scale;
+        let (width, height) = mq::measure_text(text, font_size * scale);
+        (width / scale, height / scale)
+    }
+
+    fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) {
         mq::draw_line(x1, y1, x2, y2, thickness, color);
     }
+
+    // The scene's y axis points up, so the texture coordinates are flipped to
+    // keep the image upright.
+    fn draw_texture(&mut self, texture: Texture2D, x: f32, y: f32, w: f32, h: f32) {
+        let gl = unsafe { mq::get_internal_gl() }.quad_gl;
+        gl.texture(Some(texture));
+        gl.draw_mode(DrawMode::Triangles);
+        let vertices = [
+            Vertex::new(x, y, 0., 0., 1., colors::WHITE),
+            Vertex::new(x + w, y, 0., 1., 1., colors::WHITE),
+            Vertex::new(x + w, y + h, 0., 1., 0., colors::WHITE),
+            Vertex::new(x, y + h, 0., 0., 0., colors::WHITE),
+        ];
+        gl.geometry(&vertices, &[0, 1, 2, 0, 2, 3]);
+        gl.texture(None);
+    }
+}
+
+// Uploads the image to the GPU. Needs the macroquad context, so it can only be
+// called once the application is running.
+pub fn load_texture(image: &Image) -> Texture2D {
+    mq::load_texture_from_image(image)
+}
+
+#[derive(Clone, Debug)]
+pub enum DrawOp {
+    Rectangle {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        color: Color,
+    },
+    Circle {
+        x: f32,
+        y: f32,
+        r: f32,
+        color: Color,
+    },
+    CircleOutline {
+        x: f32,
+        y: f32,
+        r: f32,
+        thickness: f32,
+        color: Color,
+    },
+    Text {
+        text: String,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        thickness: f32,
+        color: Color,
+    },
+    Texture {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+    },
+}
+
+// Captures draw calls instead of rendering them, so scenes can be inspected
+// without a window.
+#[derive(Default)]
+pub struct RecordingDrawContext {
+    pub ops: Vec<DrawOp>,
+}
+
+impl DrawContext for RecordingDrawContext {
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        self.ops.push(DrawOp::Rectangle { x, y, w, h, color });
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, r: f32, color: Color) {
+        self.ops.push(DrawOp::Circle { x, y, r, color });
+    }
+
+    fn draw_circle_outline(&mut self, x: f32, y: f32, r: f32, thickness: f32, color: Color) {
+        self.ops.push(DrawOp::CircleOutline {
+            x,
+            y,
+            r,
+            thickness,
+            color,
+        });
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        self.ops.push(DrawOp::Text {
+            text: text.to_owned(),
+            x,
+            y,
+            font_size,
+            color,
+        });
+    }
+
+    // There is no font here, so assume glyphs half as wide as they are tall.
+    fn measure_text(&mut self, text: &str, font_size: f32) -> (f32, f32) {
+        (0.5 * font_size * text.chars().count() as f32, font_size)
+    }
+
+    fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) {
+        self.ops.push(DrawOp::Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            thickness,
+            color,
+        });
+    }
+
+    fn draw_texture(&mut self, _texture: Texture2D, x: f32, y: f32, w: f32, h: f32) {
+        self.ops.push(DrawOp::Texture { x, y, w, h });
+    }
+}
+
+// Plays synthetic events through a scene the way `Runner` does, but without a
+// window: every frame updates the scene, hands it the events and records what
+// it draws. Event coordinates are in scene units. The scene tests use it to
+// paint walls with a mouse drag, for example.
+pub struct SceneDriver<T: Scene> {
+    pub scene: T,
+    frame_time: f32,
+}
+
+impl<T: Scene> SceneDriver<T> {
+    pub fn new(scene: T) -> Self {
+        Self {
+            scene,
+            frame_time: 1. / 60.,
+        }
+    }
+
+    // Seconds each frame advances the scene by, 1/60 by default.
+    pub fn set_frame_time(&mut self, frame_time: f32) {
+        self.frame_time = frame_time.max(0.);
+    }
+
+    // Runs one frame in which all of the events arrive, and returns the draw
+    // calls of that frame.
+    pub fn frame(&mut self, events: impl IntoIterator<Item = Event>) -> Vec<DrawOp> {
+        self.scene.update(self.frame_time);
+        for event in events {
+            self.scene.handle_event(event);
+        }
+        let mut cx = RecordingDrawContext::default();
+        self.scene.draw(&mut cx);
+        cx.ops
+    }
+
+    // Runs one frame per event, and returns the draw calls of the last one.
+    pub fn play(&mut self, events: impl IntoIterator<Item = Event>) -> Vec<DrawOp> {
+        let mut ops = vec![];
+        for event in events {
+            ops = self.frame(Some(event));
+        }
+        ops
+    }
+
+    // Runs frames without events, e.g. to let an animation or a debounce
+    // timer finish.
+    pub fn advance(&mut self, frames: usize) -> Vec<DrawOp> {
+        let mut ops = vec![];
+        for _ in 0..frames {
+            ops = self.frame(None);
+        }
+        ops
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum MouseButton {
     Left,
     Right,
@@ -49,10 +323,210 @@ impl From<MouseButton> for mq::MouseButton {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Key {
+    Z,
+    Y,
+    LeftBracket,
+    RightBracket,
+    Minus,
+    Equal,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    D,
+    F,
+    C,
+    R,
+    LeftShift,
+    RightShift,
+    V,
+    P,
+    N,
+    T,
+    W,
+    Up,
+    Down,
+    Left,
+    Right,
+    H,
+    G,
+    Enter,
+    I,
+    B,
+    E,
+    X,
+    K,
+    S,
+    Space,
+    U,
+    J,
+    Q,
+    L,
+    M,
+    Comma,
+    Period,
+    Slash,
+    O,
+    A,
+}
+
+impl Key {
+    const ALL: [Key; 46] = [
+        Key::Z,
+        Key::Y,
+        Key::LeftBracket,
+        Key::RightBracket,
+        Key::Minus,
+        Key::Equal,
+        Key::Key1,
+        Key::Key2,
+        Key::Key3,
+        Key::Key4,
+        Key::Key5,
+        Key::D,
+        Key::F,
+        Key::C,
+        Key::R,
+        Key::LeftShift,
+        Key::RightShift,
+        Key::V,
+        Key::P,
+        Key::N,
+        Key::T,
+        Key::W,
+        Key::Up,
+        Key::Down,
+        Key::Left,
+        Key::Right,
+        Key::H,
+        Key::G,
+        Key::Enter,
+        Key::I,
+        Key::B,
+        Key::E,
+        Key::X,
+        Key::K,
+        Key::S,
+        Key::Space,
+        Key::U,
+        Key::J,
+        Key::Q,
+        Key::L,
+        Key::M,
+        Key::Comma,
+        Key::Period,
+        Key::Slash,
+        Key::O,
+        Key::A,
+    ];
+}
+
+impl From<Key> for mq::KeyCode {
+    fn from(other: Key) -> Self {
+        match other {
+            Key::Z => mq::KeyCode::Z,
+            Key::Y => mq::KeyCode::Y,
+            Key::LeftBracket => mq::KeyCode::LeftBracket,
+            Key::RightBracket => mq::KeyCode::RightBracket,
+            Key::Minus => mq::KeyCode::Minus,
+            Key::Equal => mq::KeyCode::Equal,
+            Key::Key1 => mq::KeyCode::Key1,
+            Key::Key2 => mq::KeyCode::Key2,
+            Key::Key3 => mq::KeyCode::Key3,
+            Key::Key4 => mq::KeyCode::Key4,
+            Key::Key5 => mq::KeyCode::Key5,
+            Key::D => mq::KeyCode::D,
+            Key::F => mq::KeyCode::F,
+            Key::C => mq::KeyCode::C,
+            Key::R => mq::KeyCode::R,
+            Key::LeftShift => mq::KeyCode::LeftShift,
+            Key::RightShift => mq::KeyCode::RightShift,
+            Key::V => mq::KeyCode::V,
+            Key::P => mq::KeyCode::P,
+            Key::N => mq::KeyCode::N,
+            Key::T => mq::KeyCode::T,
+            Key::W => mq::KeyCode::W,
+            Key::Up => mq::KeyCode::Up,
+            Key::Down => mq::KeyCode::Down,
+            Key::Left => mq::KeyCode::Left,
+            Key::Right => mq::KeyCode::Right,
+            Key::H => mq::KeyCode::H,
+            Key::G => mq::KeyCode::G,
+            Key::Enter => mq::KeyCode::Enter,
+            Key::I => mq::KeyCode::I,
+            Key::B => mq::KeyCode::B,
+            Key::E => mq::KeyCode::E,
+            Key::X => mq::KeyCode::X,
+            Key::K => mq::KeyCode::K,
+            Key::S => mq::KeyCode::S,
+            Key::Space => mq::KeyCode::Space,
+            Key::U => mq::KeyCode::U,
+            Key::J => mq::KeyCode::J,
+            Key::Q => mq::KeyCode::Q,
+            Key::L => mq::KeyCode::L,
+            Key::M => mq::KeyCode::M,
+            Key::Comma => mq::KeyCode::Comma,
+            Key::Period => mq::KeyCode::Period,
+            Key::Slash => mq::KeyCode::Slash,
+            Key::O => mq::KeyCode::O,
+            Key::A => mq::KeyCode::A,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Event {
     MouseDown { button: MouseButton, x: f32, y: f32 },
     MouseUp { button: MouseButton, x: f32, y: f32 },
     MouseMoved { x: f32, y: f32 },
+    KeyDown { key: Key },
+    KeyUp { key: Key },
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A rectangle of the window in pixels, with y growing downwards like mouse
+// positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ScreenRegion {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl ScreenRegion {
+    // Splits the screen into `count` equal columns, left to right.
+    fn columns(screen_width: f32, screen_height: f32, count: usize) -> Vec<Self> {
+        let width = screen_width / count as f32;
+        (0..count)
+            .map(|i| ScreenRegion {
+                x: i as f32 * width,
+                y: 0.,
+                width,
+                height: screen_height,
+            })
+            .collect()
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// Picks the pane that receives new input: the one that still holds a pressed
+// button or touch, otherwise the one under the cursor. A lone pane gets
+// everything, even with the cursor outside of the window.
+fn route_input(regions: &[ScreenRegion], captured: Option<usize>, x: f32, y: f32) -> Option<usize> {
+    if regions.len() == 1 {
+        return Some(0);
+    }
+    captured.or_else(|| regions.iter().position(|region| region.contains(x, y)))
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -61,60 +535,208 @@ pub enum Event {
 struct EventTracker {
     scene_width: f32,
     scene_height: f32,
+    region: Option<ScreenRegion>,
     down_mouse_buttons: HashSet<MouseButton>,
+    down_keys: HashSet<Key>,
     mouse_position: (f32, f32),
+    active_touch: Option<u64>,
 }
 
 impl EventTracker {
-    fn generate_events<'a>(&'a mut self) -> impl Iterator<Item = Event> + 'a {
+    fn is_capturing(&self) -> bool {
+        !self.down_mouse_buttons.is_empty() || self.active_touch.is_some()
+    }
+
+    // An inactive tracker doesn't start anything new, but still releases the
+    // buttons and keys it saw pressed so the scene never misses an up event.
+    fn generate_events(&mut self, active: bool) -> impl Iterator<Item = Event> {
         let (screen_mouse_x, screen_mouse_y) = mq::mouse_position();
         let (mouse_x, mouse_y) = self.translate_coordinates(screen_mouse_x, screen_mouse_y);
 
-        let move_event = if (mouse_x, mouse_y) != self.mouse_position {
+        let mut events = vec![];
+
+        for &button in &[MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            let is_down = mq::is_mouse_button_down(button.into());
+            let was_down = self.down_mouse_buttons.contains(&button);
+            match (is_down, was_down) {
+                (true, false) if active => {
+                    self.down_mouse_buttons.insert(button);
+                    events.push(Event::MouseDown {
+                        button,
+                        x: mouse_x,
+                        y: mouse_y,
+                    });
+                }
+                (false, true) => {
+                    self.down_mouse_buttons.remove(&button);
+                    events.push(Event::MouseUp {
+                        button,
+                        x: mouse_x,
+                        y: mouse_y,
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        for &key in &Key::ALL {
+            let is_down = mq::is_key_down(key.into());
+            let was_down = self.down_keys.contains(&key);
+            match (is_down, was_down) {
+                (true, false) if active => {
+                    self.down_keys.insert(key);
+                    events.push(Event::KeyDown { key });
+                }
+                (false, true) => {
+                    self.down_keys.remove(&key);
+                    events.push(Event::KeyUp { key });
+                }
+                _ => (),
+            }
+        }
+
+        if active && (mouse_x, mouse_y) != self.mouse_position {
             self.mouse_position = (mouse_x, mouse_y);
-            Some(Event::MouseMoved {
+            events.push(Event::MouseMoved {
                 x: mouse_x,
                 y: mouse_y,
-            })
-        } else {
-            None
-        };
-
-        let click_events = [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
-            .iter()
-            .filter_map(move |&button| {
-                let is_down = mq::is_mouse_button_down(button.into());
-                let was_down = self.down_mouse_buttons.contains(&button);
-                match (is_down, was_down) {
-                    (true, false) => {
-                        self.down_mouse_buttons.insert(button);
-                        Some(Event::MouseDown {
-                            button,
-                            x: mouse_x,
-                            y: mouse_y,
-                        })
-                    }
-                    (false, true) => {
-                        self.down_mouse_buttons.remove(&button);
-                        Some(Event::MouseUp {
-                            button,
-                            x: mouse_x,
-                            y: mouse_y,
-                        })
-                    }
-                    _ => None,
-                }
             });
+        }
+
+        if active || self.active_touch.is_some() {
+            for touch in mq::touches() {
+                let (x, y) = self.translate_coordinates(touch.position.x(), touch.position.y());
+                self.track_touch(touch.id, touch.phase, x, y, &mut events);
+            }
+        }
 
-        click_events.chain(move_event.into_iter())
+        events.into_iter()
+    }
+
+    // Touches are reported as left mouse button drags. Only the first active
+    // touch is followed; any other fingers are ignored until it is lifted.
+    fn track_touch(&mut self, id: u64, phase: mq::TouchPhase, x: f32, y: f32, events: &mut Vec<Event>) {
+        match self.active_touch {
+            None if matches!(phase, mq::TouchPhase::Started) => {
+                self.active_touch = Some(id);
+                events.push(Event::MouseMoved { x, y });
+                events.push(Event::MouseDown {
+                    button: MouseButton::Left,
+                    x,
+                    y,
+                });
+            }
+            Some(active_id) if active_id == id => match phase {
+                mq::TouchPhase::Started | mq::TouchPhase::Stationary => (),
+                mq::TouchPhase::Moved => events.push(Event::MouseMoved { x, y }),
+                mq::TouchPhase::Ended | mq::TouchPhase::Cancelled => {
+                    self.active_touch = None;
+                    events.push(Event::MouseUp {
+                        button: MouseButton::Left,
+                        x,
+                        y,
+                    });
+                }
+            },
+            _ => (),
+        }
     }
 
     fn translate_coordinates(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
-        let screen_width = mq::screen_width();
-        let screen_height = mq::screen_height();
+        let region = self
+            .region
+            .unwrap_or_else(|| ScreenRegion::columns(mq::screen_width(), mq::screen_height(), 1)[0]);
+        Viewport::in_region(
+            self.scene_width,
+            self.scene_height,
+            region,
+            mq::screen_width(),
+            mq::screen_height(),
+        )
+        .screen_to_scene(screen_x, screen_y)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Maps the scene onto a region of the screen with a uniform scale, centering
+// it and leaving empty bars on the sides that don't fit, so cells stay square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Viewport {
+    scene_width: f32,
+    scene_height: f32,
+    region: ScreenRegion,
+    // Size of everything the camera renders to, the region is a part of it.
+    screen_width: f32,
+    screen_height: f32,
+    scale: f32,
+}
+
+impl Viewport {
+    fn new(scene_width: f32, scene_height: f32, screen_width: f32, screen_height: f32) -> Self {
+        let region = ScreenRegion {
+            x: 0.,
+            y: 0.,
+            width: screen_width,
+            height: screen_height,
+        };
+        Self::in_region(scene_width, scene_height, region, screen_width, screen_height)
+    }
+
+    fn in_region(
+        scene_width: f32,
+        scene_height: f32,
+        region: ScreenRegion,
+        screen_width: f32,
+        screen_height: f32,
+    ) -> Self {
+        Self {
+            scene_width,
+            scene_height,
+            region,
+            screen_width,
+            screen_height,
+            scale: (region.width / scene_width).min(region.height / scene_height),
+        }
+    }
+
+    fn region_center(&self) -> (f32, f32) {
         (
-            (screen_x / screen_width) * self.scene_width,
-            (1. - (screen_y / screen_height)) * self.scene_height,
+            self.region.x + self.region.width / 2.,
+            self.region.y + self.region.height / 2.,
+        )
+    }
+
+    fn camera(&self) -> mq::Camera2D {
+        // The offset is in normalized device coordinates, where y points up.
+        let (center_x, center_y) = self.region_center();
+        mq::Camera2D {
+            zoom: mq::vec2(
+                2. * self.scale / self.screen_width,
+                2. * self.scale / self.screen_height,
+            ),
+            target: mq::vec2(self.scene_width / 2., self.scene_height / 2.),
+            offset: mq::vec2(
+                2. * center_x / self.screen_width - 1.,
+                1. - 2. * center_y / self.screen_height,
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn screen_to_scene(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.region_center();
+        (
+            (screen_x - center_x) / self.scale + self.scene_width / 2.,
+            (center_y - screen_y) / self.scale + self.scene_height / 2.,
+        )
+    }
+
+    fn scene_to_screen(&self, scene_x: f32, scene_y: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.region_center();
+        (
+            (scene_x - self.scene_width / 2.) * self.scale + center_x,
+            center_y - (scene_y - self.scene_height / 2.) * self.scale,
         )
     }
 }
@@ -131,42 +753,145 @@ pub struct SceneConfig {
 pub trait Scene {
     fn config(&self) -> SceneConfig;
     fn update(&mut self, delta: f32);
-    fn draw(&mut self, cx: &mut DrawContext);
+    fn draw(&mut self, cx: &mut impl DrawContext);
     fn handle_event(&mut self, event: Event);
+
+    // Called once by the runner before the first frame, when the macroquad
+    // context and camera are already set up.
+    fn on_enter(&mut self) {}
+
+    // Called when the window (or the canvas on wasm) changes size, after the
+    // camera has been updated. Sizes are in screen pixels and cover the part
+    // of the window given to this scene.
+    fn on_resize(&mut self, _screen_width: f32, _screen_height: f32) {}
+
+    // Number of steps in the current animation and a way to jump to any of
+    // them, used to export the animation frame by frame.
+    fn animation_length(&self) -> usize {
+        0
+    }
+
+    fn seek_animation(&mut self, _step: usize) {}
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct Runner<T: Scene> {
+// Exports write at most this many frames, taking every n-th animation step
+// when the configured stride would produce more.
+const MAX_EXPORT_FRAMES: usize = 500;
+
+// Names screenshots and exports after the current second, so that saving
+// again later doesn't overwrite the earlier files.
+#[cfg(not(target_arch = "wasm32"))]
+fn timestamped_name(prefix: &str, extension: &str) -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    format!("{}-{}{}", prefix, timestamp, extension)
+}
+
+// One scene and the part of the window it is shown in.
+struct Pane<T: Scene> {
     scene: Rc<RefCell<T>>,
     scene_config: SceneConfig,
     event_tracker: EventTracker,
+    region: ScreenRegion,
+    camera: mq::Camera2D,
 }
 
-impl<T: Scene> Runner<T> {
-    pub fn new(scene: Rc<RefCell<T>>) -> Self {
+impl<T: Scene> Pane<T> {
+    fn new(scene: Rc<RefCell<T>>, region: ScreenRegion) -> Self {
         let scene_config = scene.borrow().config();
-
-        Self {
+        let mut pane = Self {
             scene,
             scene_config,
             event_tracker: EventTracker {
-                scene_width: scene_config.width,
-                scene_height: scene_config.height,
+                region: Some(region),
                 ..Default::default()
             },
+            region,
+            camera: Default::default(),
+        };
+        pane.set_scene_config(scene_config);
+        pane
+    }
+
+    fn viewport(&self) -> Viewport {
+        Viewport::in_region(
+            self.scene_config.width,
+            self.scene_config.height,
+            self.region,
+            mq::screen_width(),
+            mq::screen_height(),
+        )
+    }
+
+    fn set_scene_config(&mut self, config: SceneConfig) {
+        self.scene_config = config;
+        self.event_tracker.scene_width = config.width;
+        self.event_tracker.scene_height = config.height;
+        self.camera = self.viewport().camera();
+    }
+
+    fn set_region(&mut self, region: ScreenRegion) {
+        self.region = region;
+        self.event_tracker.region = Some(region);
+        self.set_scene_config(self.scene_config);
+    }
+
+    fn draw(&self, scene: &mut T) {
+        mq::set_camera(self.camera);
+        scene.draw(&mut MacroquadDrawContext {
+            camera: self.camera,
+            viewport: self.viewport(),
+            text_camera: None,
+        });
+    }
+}
+
+pub struct Runner<T: Scene> {
+    panes: Vec<Pane<T>>,
+    screen_size: (f32, f32),
+    export_stride: usize,
+}
+
+impl<T: Scene> Runner<T> {
+    pub fn new(scene: Rc<RefCell<T>>) -> Self {
+        Self::with_scenes(vec![scene])
+    }
+
+    // Shows two scenes side by side. Each one gets its own half of the window
+    // and only sees input while the cursor is over it, or while it holds a
+    // button pressed there.
+    pub fn split(left: Rc<RefCell<T>>, right: Rc<RefCell<T>>) -> Self {
+        Self::with_scenes(vec![left, right])
+    }
+
+    fn with_scenes(scenes: Vec<Rc<RefCell<T>>>) -> Self {
+        let screen_size = (mq::screen_width(), mq::screen_height());
+        let regions = ScreenRegion::columns(screen_size.0, screen_size.1, scenes.len());
+        Self {
+            panes: scenes
+                .into_iter()
+                .zip(regions)
+                .map(|(scene, region)| Pane::new(scene, region))
+                .collect(),
+            screen_size,
+            export_stride: 1,
         }
     }
 
+    // Animation steps between exported frames.
+    pub fn set_export_stride(&mut self, stride: usize) {
+        self.export_stride = stride.max(1);
+    }
+
     pub async fn run(&mut self) {
-        mq::set_camera(mq::Camera2D {
-            zoom: mq::vec2(
-                1. / self.scene_config.width * 2.,
-                1. / self.scene_config.height * 2.,
-            ),
-            target: mq::vec2(self.scene_config.width / 2., self.scene_config.height / 2.),
-            ..Default::default()
-        });
+        for pane in &self.panes {
+            mq::set_camera(pane.camera);
+            pane.scene.borrow_mut().on_enter();
+        }
 
         let mut prev_update_time = mq::get_time();
 
@@ -175,22 +900,408 @@ impl<T: Scene> Runner<T> {
             let delta = (now - prev_update_time) as f32;
             prev_update_time = now;
 
-            self.update_and_draw_scene(delta);
+            self.update_and_draw_scenes(delta);
 
             mq::next_frame().await
         }
     }
 
-    fn update_and_draw_scene(&mut self, delta: f32) {
-        let mut scene = self.scene.borrow_mut();
+    fn update_and_draw_scenes(&mut self, delta: f32) {
+        // The camera zoom depends on the screen size, so it has to follow the
+        // window before any events are translated into scene coordinates.
+        let screen_size = (mq::screen_width(), mq::screen_height());
+        if screen_size != self.screen_size {
+            self.screen_size = screen_size;
+            let regions = ScreenRegion::columns(screen_size.0, screen_size.1, self.panes.len());
+            for (pane, region) in self.panes.iter_mut().zip(regions) {
+                pane.set_region(region);
+                mq::set_camera(pane.camera);
+                pane.scene.borrow_mut().on_resize(region.width, region.height);
+            }
+        }
+
+        let regions: Vec<ScreenRegion> = self.panes.iter().map(|pane| pane.region).collect();
+        let captured = self.panes.iter().position(|pane| pane.event_tracker.is_capturing());
+        let (mouse_x, mouse_y) = mq::mouse_position();
+        let active = route_input(&regions, captured, mouse_x, mouse_y);
+
+        let mut screenshot_requested = false;
+        let mut export_requested = false;
+        for (i, pane) in self.panes.iter_mut().enumerate() {
+            let scene = pane.scene.clone();
+            let mut scene = scene.borrow_mut();
+
+            scene.update(delta);
+
+            for event in pane.event_tracker.generate_events(active == Some(i)) {
+                match event {
+                    Event::KeyDown { key: Key::P } => screenshot_requested = true,
+                    Event::KeyDown { key: Key::E } => export_requested = true,
+                    _ => (),
+                }
+                scene.handle_event(event);
+            }
+
+            let config = scene.config();
+            if (config.width, config.height) != (pane.scene_config.width, pane.scene_config.height) {
+                pane.set_scene_config(config);
+            }
+        }
+
+        // Screenshots and exports are about the scene the user is looking at.
+        let target = active.unwrap_or(0);
+
+        if export_requested {
+            let pane = &self.panes[target];
+            let scene = pane.scene.clone();
+            self.export_animation(pane, &mut *scene.borrow_mut());
+        }
+
+        mq::clear_background(self.panes[0].scene_config.bgcolor);
+        for pane in &self.panes {
+            pane.draw(&mut *pane.scene.borrow_mut());
+        }
+
+        if screenshot_requested {
+            self.save_screenshot(&self.panes[target]);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_animation(&self, pane: &Pane<T>, scene: &mut T) {
+        let length = scene.animation_length();
+        if length == 0 {
+            println!("There is no animation to export");
+            return;
+        }
+        let directory = timestamped_name("frames", "");
+        match self.write_animation_frames(pane, scene, length, &directory) {
+            Ok(count) => println!("Saved {} frames to {}", count, directory),
+            Err(err) => eprintln!("Failed to export frames to {}: {}", directory, err),
+        }
+        scene.seek_animation(length);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn export_animation(&self, _pane: &Pane<T>, _scene: &mut T) {}
+
+    // Draws the scene into an off-screen target at the window's resolution once
+    // per exported step and saves each image as a numbered PNG.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_animation_frames(
+        &self,
+        pane: &Pane<T>,
+        scene: &mut T,
+        length: usize,
+        directory: &str,
+    ) -> image::ImageResult<usize> {
+        std::fs::create_dir_all(directory)?;
+
+        let viewport = pane.viewport();
+        let width = ((viewport.scene_width * viewport.scale).round() as u32).max(1);
+        let height = ((viewport.scene_height * viewport.scale).round() as u32).max(1);
+        let viewport = Viewport::new(viewport.scene_width, viewport.scene_height, width as f32, height as f32);
+        let target = mq::render_target(width, height);
+        let camera = mq::Camera2D {
+            render_target: Some(target),
+            ..viewport.camera()
+        };
+        let text_camera = mq::Camera2D {
+            render_target: Some(target),
+            ..mq::Camera2D::from_display_rect(Rect::new(0., 0., width as f32, height as f32))
+        };
 
-        scene.update(delta);
+        let stride = self.export_stride.max((length + MAX_EXPORT_FRAMES - 1) / MAX_EXPORT_FRAMES);
+        let mut steps: Vec<usize> = (stride..length).step_by(stride).collect();
+        steps.push(length);
 
-        for event in self.event_tracker.generate_events() {
-            scene.handle_event(event);
+        let result = steps.iter().enumerate().try_for_each(|(i, &step)| {
+            scene.seek_animation(step);
+            mq::set_camera(camera);
+            mq::clear_background(pane.scene_config.bgcolor);
+            scene.draw(&mut MacroquadDrawContext {
+                camera,
+                viewport,
+                text_camera: Some(text_camera),
+            });
+            unsafe { mq::get_internal_gl() }.flush();
+
+            let data = target.texture.get_texture_data();
+            let frame = image::RgbaImage::from_raw(width, height, data.bytes)
+                .ok_or(image::ImageError::DimensionError)?;
+            // Like the framebuffer, the texture is read bottom row first.
+            image::imageops::flip_vertical(&frame)
+                .save(format!("{}/frame-{:05}.png", directory, i + 1))
+                .map_err(image::ImageError::from)
+        });
+
+        target.delete();
+        mq::set_camera(pane.camera);
+        result.map(|()| steps.len())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_screenshot(&self, pane: &Pane<T>) {
+        let path = timestamped_name("screenshot", ".png");
+        match self.write_screenshot(pane, &path) {
+            Ok(()) => println!("Saved screenshot to {}", path),
+            Err(err) => eprintln!("Failed to save screenshot to {}: {}", path, err),
         }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save_screenshot(&self, _pane: &Pane<T>) {}
+
+    // Saves the part of the window covered by the scene, without the
+    // letterboxing bars or the other pane. The output has the window's
+    // resolution.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_screenshot(&self, pane: &Pane<T>, path: &str) -> image::ImageResult<()> {
+        unsafe { mq::get_internal_gl() }.flush();
+        let screen = mq::get_screen_data();
+        let (screen_width, screen_height) = (screen.width as u32, screen.height as u32);
+        let frame = image::RgbaImage::from_raw(screen_width, screen_height, screen.bytes)
+            .ok_or(image::ImageError::DimensionError)?;
+        // The framebuffer is read bottom row first.
+        let mut frame = image::imageops::flip_vertical(&frame);
+
+        // The framebuffer may have more pixels than the logical screen size.
+        let pixel_ratio = screen_width as f32 / mq::screen_width();
+        let region = ScreenRegion {
+            x: pane.region.x * pixel_ratio,
+            y: pane.region.y * pixel_ratio,
+            width: pane.region.width * pixel_ratio,
+            height: pane.region.height * pixel_ratio,
+        };
+        let viewport = Viewport::in_region(
+            pane.scene_config.width,
+            pane.scene_config.height,
+            region,
+            screen_width as f32,
+            screen_height as f32,
+        );
+        let (left, top) = viewport.scene_to_screen(0., viewport.scene_height);
+        let x = (left.round().max(0.) as u32).min(screen_width);
+        let y = (top.round().max(0.) as u32).min(screen_height);
+        let width = ((viewport.scene_width * viewport.scale).round() as u32).min(screen_width - x);
+        let height = ((viewport.scene_height * viewport.scale).round() as u32).min(screen_height - y);
+        image::imageops::crop(&mut frame, x, y, width, height)
+            .to_image()
+            .save(path)
+            .map_err(image::ImageError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_batches_fit_in_one_quad_gl_draw_call() {
+        assert!(VERTICES_PER_RECT * MAX_RECTS_PER_BATCH <= QUAD_GL_MAX_VERTICES);
+        assert!(INDICES_PER_RECT * MAX_RECTS_PER_BATCH <= QUAD_GL_MAX_INDICES);
+        // Indices are u16, so the last vertex of a batch has to be addressable.
+        assert!(VERTICES_PER_RECT * MAX_RECTS_PER_BATCH <= u16::MAX as usize + 1);
+    }
+
+    #[test]
+    fn rect_geometry_indexes_its_own_vertices() {
+        let rects = vec![Rect::new(0., 0., 1., 1.); MAX_RECTS_PER_BATCH];
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        push_rect_geometry(&rects, colors::BLACK, &mut vertices, &mut indices);
+
+        assert_eq!(vertices.len(), VERTICES_PER_RECT * rects.len());
+        assert_eq!(indices.len(), INDICES_PER_RECT * rects.len());
+        assert_eq!(&indices[..6], &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(indices.iter().max().map(|&i| i as usize), Some(vertices.len() - 1));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn timestamped_names_keep_prefix_and_extension() {
+        let name = timestamped_name("screenshot", ".png");
+        let timestamp = name
+            .strip_prefix("screenshot-")
+            .and_then(|rest| rest.strip_suffix(".png"))
+            .expect("name should keep its prefix and extension");
+        assert!(timestamp.parse::<u64>().map_or(false, |secs| secs > 0));
+        assert!(timestamped_name("frames", "").starts_with("frames-"));
+    }
+
+    fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-3 && (actual.1 - expected.1).abs() < 1e-3,
+            "{:?} is not close to {:?}",
+            actual,
+            expected
+        );
+    }
+
+    // A wide, a tall and a square scene on a wide, a tall and a square screen.
+    const SCENE_SIZES: &[(f32, f32)] = &[(4000., 1000.), (1000., 4000.), (1000., 1000.)];
+    const SCREEN_SIZES: &[(f32, f32)] = &[(1600., 900.), (600., 1000.), (800., 800.)];
+
+    #[test]
+    fn viewport_round_trips_scene_coordinates() {
+        for &(scene_width, scene_height) in SCENE_SIZES {
+            for &(screen_width, screen_height) in SCREEN_SIZES {
+                let viewport = Viewport::new(scene_width, scene_height, screen_width, screen_height);
+                for &point in &[(0., 0.), (scene_width, scene_height), (123., 456.)] {
+                    let (x, y) = viewport.scene_to_screen(point.0, point.1);
+                    assert_close(viewport.screen_to_scene(x, y), point);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn viewport_letterboxes_and_centers_the_scene() {
+        for &(scene_width, scene_height) in SCENE_SIZES {
+            for &(screen_width, screen_height) in SCREEN_SIZES {
+                let viewport = Viewport::new(scene_width, scene_height, screen_width, screen_height);
+                // y points up in the scene and down on the screen.
+                let (left, bottom) = viewport.scene_to_screen(0., 0.);
+                let (right, top) = viewport.scene_to_screen(scene_width, scene_height);
+                let (width, height) = (right - left, bottom - top);
+
+                // Cells stay square and the scene touches two opposite edges.
+                assert!((width / scene_width - height / scene_height).abs() < 1e-4);
+                assert!((width - screen_width).abs() < 1e-3 || (height - screen_height).abs() < 1e-3);
+                assert!(width <= screen_width + 1e-3 && height <= screen_height + 1e-3);
+                assert_close(
+                    ((left + right) / 2., (top + bottom) / 2.),
+                    (screen_width / 2., screen_height / 2.),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn viewport_in_a_region_maps_into_that_region() {
+        let regions = ScreenRegion::columns(1600., 900., 2);
+        let viewport = Viewport::in_region(1000., 1000., regions[1], 1600., 900.);
+
+        let (left, _) = viewport.scene_to_screen(0., 0.);
+        let (right, _) = viewport.scene_to_screen(1000., 1000.);
+        assert_close((left, right), (1200. - 400., 1200. + 400.));
+        assert!(regions[1].contains(left, 450.) && regions[1].contains(right - 1., 450.));
+        assert_close(viewport.screen_to_scene(1200., 450.), (500., 500.));
+    }
+
+    fn feed_touches(phases: &[(u64, mq::TouchPhase, f32)]) -> (EventTracker, Vec<Event>) {
+        let mut tracker = EventTracker::default();
+        let mut events = vec![];
+        for &(id, phase, x) in phases {
+            tracker.track_touch(id, phase, x, 0., &mut events);
+        }
+        (tracker, events)
+    }
+
+    fn left_down(x: f32) -> Event {
+        Event::MouseDown {
+            button: MouseButton::Left,
+            x,
+            y: 0.,
+        }
+    }
+
+    fn left_up(x: f32) -> Event {
+        Event::MouseUp {
+            button: MouseButton::Left,
+            x,
+            y: 0.,
+        }
+    }
+
+    fn moved(x: f32) -> Event {
+        Event::MouseMoved { x, y: 0. }
+    }
+
+    #[test]
+    fn touch_becomes_a_left_button_drag() {
+        let (tracker, events) = feed_touches(&[
+            (7, mq::TouchPhase::Started, 1.),
+            (7, mq::TouchPhase::Stationary, 1.),
+            (7, mq::TouchPhase::Moved, 2.),
+            (7, mq::TouchPhase::Ended, 3.),
+        ]);
+        assert_eq!(events, vec![moved(1.), left_down(1.), moved(2.), left_up(3.)]);
+        assert!(!tracker.is_capturing());
+    }
+
+    #[test]
+    fn only_the_first_touch_is_followed() {
+        let (tracker, events) = feed_touches(&[
+            (1, mq::TouchPhase::Started, 1.),
+            (2, mq::TouchPhase::Started, 5.),
+            (2, mq::TouchPhase::Moved, 6.),
+            (1, mq::TouchPhase::Moved, 2.),
+            (2, mq::TouchPhase::Ended, 6.),
+        ]);
+        assert_eq!(events, vec![moved(1.), left_down(1.), moved(2.)]);
+        assert!(tracker.is_capturing());
+    }
+
+    #[test]
+    fn cancelled_touch_releases_the_button_and_frees_the_tracker() {
+        let (tracker, events) = feed_touches(&[
+            (1, mq::TouchPhase::Started, 1.),
+            (1, mq::TouchPhase::Cancelled, 1.),
+            (2, mq::TouchPhase::Moved, 4.),
+            (2, mq::TouchPhase::Started, 4.),
+        ]);
+        assert_eq!(
+            events,
+            vec![moved(1.), left_down(1.), left_up(1.), moved(4.), left_down(4.)]
+        );
+        assert!(tracker.is_capturing());
+    }
+
+    #[test]
+    fn screen_columns_tile_the_screen() {
+        let regions = ScreenRegion::columns(1000., 600., 2);
+        assert_eq!(
+            regions,
+            vec![
+                ScreenRegion {
+                    x: 0.,
+                    y: 0.,
+                    width: 500.,
+                    height: 600.,
+                },
+                ScreenRegion {
+                    x: 500.,
+                    y: 0.,
+                    width: 500.,
+                    height: 600.,
+                },
+            ]
+        );
+        // The shared edge belongs to the right column only.
+        assert!(!regions[0].contains(500., 300.));
+        assert!(regions[1].contains(500., 300.));
+    }
+
+    #[test]
+    fn input_goes_to_the_pane_under_the_cursor() {
+        let regions = ScreenRegion::columns(1000., 600., 2);
+        assert_eq!(route_input(&regions, None, 100., 300.), Some(0));
+        assert_eq!(route_input(&regions, None, 900., 300.), Some(1));
+        assert_eq!(route_input(&regions, None, 1200., 300.), None);
+        assert_eq!(route_input(&regions, None, 100., -1.), None);
+    }
+
+    #[test]
+    fn a_pressed_pane_keeps_the_input_until_released() {
+        let regions = ScreenRegion::columns(1000., 600., 2);
+        assert_eq!(route_input(&regions, Some(0), 900., 300.), Some(0));
+        assert_eq!(route_input(&regions, Some(1), -50., 300.), Some(1));
+    }
 
-        mq::clear_background(self.scene_config.bgcolor);
-        scene.draw(&mut DrawContext {});
+    #[test]
+    fn a_lone_pane_gets_all_input() {
+        let regions = ScreenRegion::columns(1000., 600., 1);
+        assert_eq!(route_input(&regions, None, -50., 9000.), Some(0));
     }
 }
@@ -1,22 +1,94 @@
 pub use crate::runner::{colors, Color};
 
-use crate::grid::Grid;
-use crate::runner::{DrawContext, Event, MouseButton, Scene, SceneConfig};
+use crate::grid::{Connectivity, CoordConvention, Grid, GridTransform};
+use crate::history::{Edit, History};
+use crate::maze::{Maze, ParseMazeError};
+use crate::pathfind::builtin::{self, Algorithm, GoalMode};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pathfind::background::BackgroundSearch;
+use crate::pathfind::SearchOptions;
+use crate::perf::FrameTimer;
+use crate::theme::{Theme, WallStyle};
+
+use std::collections::{HashMap, HashSet};
+use crate::runner::{self, DrawContext, Event, Image, Key, MouseButton, Rect, Scene, SceneConfig, Texture2D};
+
+const MAX_HISTORY_DEPTH: usize = 100;
+const MIN_GRID_SIZE: usize = 2;
+pub const MAX_GRID_SIZE: usize = 100;
+const DEFAULT_FPS_WINDOW: usize = 60;
+// Seconds without edits before live mode re-runs the search.
+const LIVE_SEARCH_DELAY: f32 = 0.3;
+// Seconds between the last edit and re-checking whether the maze is solvable.
+const SOLVABLE_CHECK_DELAY: f32 = 0.1;
+const RANDOM_FILL_PROBABILITY: f32 = 0.3;
+const TOOLTIP_FONT_SIZE: f32 = 40.;
+const TOOLTIP_PADDING: f32 = 10.;
+const TOOLTIP_BACKGROUND: Color = Color::new(1., 1., 0.9, 0.9);
+// The legend gets its own strip to the right of the grid, so it never covers
+// any cells.
+const LEGEND_WIDTH: f32 = 550.;
+// Distance between neighbouring lines of the grid in scene units. Everything
+// drawn in a cell is sized relative to it, with exact fractions so that the
+// default size gives round numbers.
+pub const CELL_SIZE: f32 = 100.;
+// Bars are centered on the lines between cells, so their width never moves the
+// cells themselves. The grid keeps a margin of half the default bar around it,
+// which puts the line before cell `i` at `GRID_MARGIN + i * CELL_SIZE`.
+const DEFAULT_BAR_WIDTH: f32 = CELL_SIZE / 20.;
+const GRID_MARGIN: f32 = DEFAULT_BAR_WIDTH / 2.;
+const MAX_BAR_WIDTH: f32 = CELL_SIZE / 5.;
+const MARKER_RADIUS: f32 = CELL_SIZE * 9. / 20.;
+const MARKER_RING_WIDTH: f32 = CELL_SIZE / 10.;
+const WAYPOINT_RADIUS: f32 = CELL_SIZE * 7. / 20.;
+const CELL_FONT_SIZE: f32 = CELL_SIZE * 2. / 5.;
+// Wall decorations of `WallStyle`.
+const WALL_BORDER_WIDTH: f32 = CELL_SIZE / 10.;
+const WALL_HATCH_COUNT: usize = 4;
+const WALL_HATCH_WIDTH: f32 = CELL_SIZE * 3. / 50.;
+// Status lines are stacked upwards from the bottom left of the scene, one
+// `STATUS_LINE_HEIGHT` apart, see `StatusLine`.
+const STATUS_FONT_SIZE: f32 = 50.;
+const STATUS_LINE_HEIGHT: f32 = 60.;
+const STATUS_MARGIN: f32 = 20.;
+const LEGEND_MARGIN: f32 = 30.;
+const LEGEND_LABEL_GAP: f32 = 20.;
+// Draw commands revealed per second of animation.
+const ANIMATION_SPEED: f32 = 100.;
+// Flashed cells pulse this many times per second.
+const FLASH_FREQUENCY: f32 = 2.;
+const FLASH_COLOR: Color = Color::new(1., 0.85, 0., 0.7);
+// The distance overlay is drawn in this many color bands.
+const DISTANCE_COLOR_STEPS: usize = 16;
+const UNREACHABLE_TINT: Color = Color::new(0., 0., 0., 0.12);
+const COMPONENT_COLORS: [Color; 8] = [
+    Color::new(0.9, 0.3, 0.3, 0.4),
+    Color::new(0.3, 0.6, 0.9, 0.4),
+    Color::new(0.3, 0.8, 0.4, 0.4),
+    Color::new(0.9, 0.7, 0.2, 0.4),
+    Color::new(0.7, 0.4, 0.9, 0.4),
+    Color::new(0.2, 0.8, 0.8, 0.4),
+    Color::new(0.9, 0.5, 0.7, 0.4),
+    Color::new(0.6, 0.6, 0.3, 0.4),
+];
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum DrawCommand {
     AddShape(Shape),
     Clear,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Shape {
+    // With an intensity in [0, 1] the square is colored by `heatmap_color`
+    // instead of `color`.
     Square {
         x: usize,
         y: usize,
         color: Color,
+        intensity: Option<f32>,
     },
     Line {
         from: (usize, usize),
@@ -29,41 +101,285 @@ pub enum Shape {
         width: f32,
         color: Color,
     },
+    Circle {
+        x: usize,
+        y: usize,
+        r: f32,
+        color: Color,
+    },
+    Text {
+        x: usize,
+        y: usize,
+        text: String,
+        color: Color,
+    },
+    // Like `Text`, but only the latest annotation of a cell is drawn.
+    Annotation {
+        x: usize,
+        y: usize,
+        text: String,
+    },
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// Produces the draw commands for a live re-run, or `None` if the search failed
+// (the searcher is responsible for reporting the error).
+pub type LiveSearcher =
+    Box<dyn FnMut(&Grid<bool>, (usize, usize), (usize, usize), &SearchOptions) -> Option<Vec<DrawCommand>>>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Endpoint {
+    Start,
+    Finish,
+}
+
+// Rows of status text, from the bottom of the scene up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StatusLine {
+    Fps,
+    // Only native builds run searches in the background.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    Search,
+    Explored,
+    Solvable,
+    Locked,
+    ClosestApproach,
+}
+
+// What a key does while keyboard editing is on. Other keys keep their usual
+// meaning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyboardAction {
+    MoveCursor(i64, i64),
+    ToggleWall,
+    SetStart,
+    SetFinish,
+}
+
+impl KeyboardAction {
+    fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::Up => Some(KeyboardAction::MoveCursor(0, 1)),
+            Key::Down => Some(KeyboardAction::MoveCursor(0, -1)),
+            Key::Left => Some(KeyboardAction::MoveCursor(-1, 0)),
+            Key::Right => Some(KeyboardAction::MoveCursor(1, 0)),
+            Key::Space => Some(KeyboardAction::ToggleWall),
+            Key::S => Some(KeyboardAction::SetStart),
+            Key::F => Some(KeyboardAction::SetFinish),
+            _ => None,
+        }
+    }
+}
+
+// Cells highlighted by `flash_cells`, counting down to zero.
+struct Flash {
+    cells: Vec<(usize, usize)>,
+    remaining: f32,
+    elapsed: f32,
+}
+
+impl Flash {
+    // Returns whether the flash is still running.
+    fn advance(&mut self, delta: f32) -> bool {
+        self.remaining -= delta;
+        self.elapsed += delta;
+        self.remaining > 0.
+    }
+
+    // Pulses from transparent to full strength and back.
+    fn alpha(&self) -> f32 {
+        0.5 - 0.5 * (2. * std::f32::consts::PI * FLASH_FREQUENCY * self.elapsed).cos()
+    }
+}
+
+#[derive(Clone, Copy)]
 enum PointerMode {
     Noop,
     SetWall,
     EraseWall,
-    SetStart,
-    SetFinish,
+    SetStart { origin: (usize, usize) },
+    SetFinish { origin: (usize, usize) },
+    FillRect { origin: (usize, usize), is_wall: bool },
 }
 
 pub struct PathtfindScene {
     grid: Grid<bool>,
     start: (usize, usize),
     finish: (usize, usize),
+    initial_size: (usize, usize),
+    initial_start: (usize, usize),
+    initial_finish: (usize, usize),
+    // Last cell under the cursor, kept while the cursor is outside of the grid
+    // so that a rectangle can still be finished there.
     active_cell: Option<(usize, usize)>,
+    cursor_in_grid: bool,
+    // The arrows move `active_cell` as a cursor, see `KeyboardAction`.
+    keyboard_mode: bool,
+    // Ignores edits made with the mouse or the keyboard cursor, see `set_locked`.
+    locked: bool,
     pointer_mode: PointerMode,
+    stroke_cell: Option<(usize, usize)>,
+    shift_held: bool,
+    live_mode: bool,
+    live_searcher: Option<LiveSearcher>,
+    live_search_timer: Option<f32>,
+    random_seed: u64,
+    show_tooltip: bool,
+    heatmap: bool,
+    // Path segments fade from the start color to the finish color.
+    gradient_path: bool,
+    // Built-in searches fall back to the closest reachable cell, and
+    // `showing_closest_approach` tells whether the current path is one.
+    closest_approach: bool,
+    showing_closest_approach: bool,
+    selected_endpoint: Option<Endpoint>,
+    waypoints: Vec<(usize, usize)>,
+    flash: Option<Flash>,
+    show_legend: bool,
+    show_distances: bool,
+    distances: Option<Grid<Option<usize>>>,
     draw_commands: Vec<DrawCommand>,
     animation_progress: f32,
+    // Start animations after the last `Clear`, which skips the exploration
+    // and only traces the final path.
+    final_path_only: bool,
+    history: History,
+    connectivity: Connectivity,
+    // How user scripts see cells, the built-in searches don't care.
+    convention: CoordConvention,
+    theme: Theme,
+    frame_timer: FrameTimer,
+    show_fps: bool,
+    show_explored_count: bool,
+    show_solvable: bool,
+    // Whether the finish is reachable, as of the last check. It stays shown
+    // while the next check waits for `solvable_timer`.
+    solvable: Option<bool>,
+    solvable_timer: Option<f32>,
+    bar_width: f32,
+    show_bars: bool,
+    reachable: Option<HashSet<(usize, usize)>>,
+    show_components: bool,
+    components: Option<Grid<Option<usize>>>,
+    // The image waits here until the macroquad context is up, see `on_enter`.
+    pending_background: Option<Image>,
+    background: Option<Texture2D>,
+    entered: bool,
+    // Native builds have no editor, the code to run comes from `main`.
+    #[cfg(not(target_arch = "wasm32"))]
+    user_code: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    background_search: Option<BackgroundSearch>,
+}
+
+fn check_endpoint_cell(
+    grid: &Grid<bool>,
+    (x, y): (usize, usize),
+    other: (usize, usize),
+) -> Result<(), &'static str> {
+    match grid.get_checked(x, y) {
+        Err(_) => Err("cell is outside of the grid"),
+        Ok(true) => Err("cell is a wall"),
+        Ok(false) if (x, y) == other => Err("start and finish must be different cells"),
+        Ok(false) => Ok(()),
+    }
 }
 
 impl PathtfindScene {
+    // For endpoints that come from outside the program, such as a shared
+    // link, where `new` would panic on bad input.
+    pub fn try_new(
+        grid: Grid<bool>,
+        start: (usize, usize),
+        finish: (usize, usize),
+    ) -> Result<Self, &'static str> {
+        check_endpoint_cell(&grid, start, finish)?;
+        check_endpoint_cell(&grid, finish, start)?;
+        Ok(Self::new(grid, start, finish))
+    }
+
     pub fn new(grid: Grid<bool>, start: (usize, usize), finish: (usize, usize)) -> Self {
+        assert!(
+            start != finish,
+            "start and finish must be different cells, both are at {:?}",
+            start
+        );
         Self {
+            initial_size: (grid.width(), grid.height()),
+            initial_start: start,
+            initial_finish: finish,
             grid,
             start,
             finish,
             active_cell: None,
+            cursor_in_grid: false,
+            keyboard_mode: false,
+            locked: false,
             pointer_mode: PointerMode::Noop,
+            stroke_cell: None,
+            shift_held: false,
+            live_mode: false,
+            live_searcher: None,
+            live_search_timer: None,
+            random_seed: 0,
+            show_tooltip: false,
+            heatmap: false,
+            gradient_path: false,
+            closest_approach: false,
+            showing_closest_approach: false,
+            selected_endpoint: None,
+            waypoints: vec![],
+            flash: None,
+            show_legend: false,
+            show_distances: false,
+            distances: None,
             draw_commands: vec![],
             animation_progress: 0.,
+            final_path_only: false,
+            history: History::new(MAX_HISTORY_DEPTH),
+            connectivity: Connectivity::default(),
+            convention: CoordConvention::default(),
+            theme: Theme::default(),
+            frame_timer: FrameTimer::new(DEFAULT_FPS_WINDOW),
+            show_fps: false,
+            show_explored_count: false,
+            show_solvable: false,
+            solvable: None,
+            solvable_timer: None,
+            bar_width: DEFAULT_BAR_WIDTH,
+            show_bars: true,
+            reachable: None,
+            show_components: false,
+            components: None,
+            pending_background: None,
+            background: None,
+            entered: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            user_code: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            background_search: None,
         }
     }
 
+    // Builds a scene from the text maze format. Missing endpoint headers
+    // default to the bottom left and top right corners.
+    pub fn from_maze_str(s: &str) -> Result<Self, ParseMazeError> {
+        let maze: Maze = s.parse()?;
+        let (width, height) = (maze.grid.width(), maze.grid.height());
+        let start = maze.start.unwrap_or((0, 0));
+        let finish = maze.finish.unwrap_or((width - 1, height - 1));
+        for &(name, (x, y)) in &[("start", start), ("finish", finish)] {
+            if maze.grid.get(x, y) {
+                return Err(ParseMazeError::EndpointOnWall { name, x, y });
+            }
+        }
+        if start == finish {
+            return Err(ParseMazeError::SameEndpoints);
+        }
+        Ok(Self::new(maze.grid, start, finish))
+    }
+
     pub fn grid(&self) -> &Grid<bool> {
         &self.grid
     }
@@ -76,22 +392,586 @@ impl PathtfindScene {
         self.finish
     }
 
+    // Cell under the cursor, or `None` when the cursor is outside of the grid.
+    pub fn active_cell(&self) -> Option<(usize, usize)> {
+        self.active_cell.filter(|_| self.cursor_in_grid)
+    }
+
+    pub fn set_start(&mut self, cell: (usize, usize)) -> Result<(), &'static str> {
+        self.check_endpoint(cell, self.finish)?;
+        self.history.commit_stroke();
+        self.move_start(cell);
+        self.history.commit_stroke();
+        Ok(())
+    }
+
+    pub fn set_finish(&mut self, cell: (usize, usize)) -> Result<(), &'static str> {
+        self.check_endpoint(cell, self.start)?;
+        self.history.commit_stroke();
+        self.move_finish(cell);
+        self.history.commit_stroke();
+        Ok(())
+    }
+
+    pub fn connectivity(&self) -> Connectivity {
+        self.connectivity
+    }
+
+    pub fn set_connectivity(&mut self, connectivity: Connectivity) {
+        if connectivity != self.connectivity {
+            self.connectivity = connectivity;
+            self.distances = None;
+            self.clear_path();
+            self.schedule_solvable_check();
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn convention(&self) -> CoordConvention {
+        self.convention
+    }
+
+    pub fn set_convention(&mut self, convention: CoordConvention) {
+        self.convention = convention;
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn bar_width(&self) -> f32 {
+        self.bar_width
+    }
+
+    pub fn set_bar_width(&mut self, width: f32) {
+        self.bar_width = width.max(0.).min(MAX_BAR_WIDTH);
+    }
+
+    pub fn show_bars(&self) -> bool {
+        self.show_bars
+    }
+
+    pub fn set_show_bars(&mut self, show_bars: bool) {
+        self.show_bars = show_bars;
+    }
+
+    // Shows the image behind the grid, stretched over all of the cells.
+    pub fn set_background_image(&mut self, image: Image) {
+        self.pending_background = Some(image);
+        if self.entered {
+            self.upload_background();
+        }
+    }
+
+    pub fn clear_background_image(&mut self) {
+        self.pending_background = None;
+        self.background = None;
+    }
+
+    fn upload_background(&mut self) {
+        if let Some(image) = self.pending_background.take() {
+            self.background = Some(runner::load_texture(&image));
+        }
+    }
+
+    pub fn live_mode(&self) -> bool {
+        self.live_mode
+    }
+
+    pub fn set_live_mode(&mut self, live_mode: bool) {
+        self.live_mode = live_mode;
+        self.live_search_timer = if live_mode { Some(0.) } else { None };
+    }
+
+    // Replaces the search live mode runs; without one it falls back to A*.
+    pub fn set_live_searcher(&mut self, searcher: LiveSearcher) {
+        self.live_searcher = Some(searcher);
+    }
+
+    pub fn set_fps_window(&mut self, frames: usize) {
+        self.frame_timer.set_window(frames);
+    }
+
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            connectivity: self.connectivity,
+            path_color: self.theme.path,
+            path_gradient: if self.gradient_path {
+                Some((self.theme.start, self.theme.finish))
+            } else {
+                None
+            },
+            heatmap: self.heatmap,
+            closest_approach: self.closest_approach,
+            convention: self.convention,
+            ..SearchOptions::default()
+        }
+    }
+
     pub fn set_draw_commands(&mut self, commands: Vec<DrawCommand>) {
         self.draw_commands = commands;
         self.animation_progress = -1.;
+        self.showing_closest_approach = false;
+    }
+
+    // Plays the current animation again from the beginning without re-running
+    // the search.
+    pub fn replay(&mut self) {
+        if !self.draw_commands.is_empty() {
+            self.animation_progress = -1.;
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_user_code(&mut self, code: String) {
+        self.user_code = Some(code);
+    }
+
+    // Starts the user code on a worker thread, or stops the running one. The
+    // result is picked up by `update` and summaries are printed to stdout.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn toggle_background_search(&mut self) {
+        if let Some(search) = self.background_search.take() {
+            search.cancel();
+            return;
+        }
+        let code = match &self.user_code {
+            Some(code) => code.clone(),
+            None => return,
+        };
+        self.clear_path();
+        self.background_search = Some(BackgroundSearch::spawn(
+            code,
+            self.grid.clone(),
+            self.start,
+            self.finish,
+            self.search_options(),
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_background_search(&mut self) {
+        let result = match self.background_search.as_ref().and_then(BackgroundSearch::poll) {
+            Some(result) => result,
+            None => return,
+        };
+        self.background_search = None;
+        match result {
+            Ok(search) => {
+                let summary = search.summary();
+                if !summary.is_empty() {
+                    println!("{}", summary);
+                }
+                self.set_draw_commands(search.draw_commands);
+            }
+            Err(traceback) => eprintln!("{}", traceback),
+        }
+    }
+
+    // A search started before an edit would finish with a stale path.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cancel_background_search(&mut self) {
+        if let Some(search) = self.background_search.take() {
+            search.cancel();
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn draw_search_indicator(&self, cx: &mut impl DrawContext) {
+        let progress = match &self.background_search {
+            Some(search) => search.progress(),
+            None => return,
+        };
+        let text = if progress == 0 {
+            "Searching...".to_owned()
+        } else {
+            format!("Searching... ({} calls)", progress)
+        };
+        self.draw_status(StatusLine::Search, &text, colors::BLACK, cx);
+    }
+
+    pub fn keyboard_mode(&self) -> bool {
+        self.keyboard_mode
+    }
+
+    // The cursor starts on the hovered cell, or on the start if there is none.
+    pub fn set_keyboard_mode(&mut self, keyboard_mode: bool) {
+        self.keyboard_mode = keyboard_mode;
+        if keyboard_mode && self.active_cell().is_none() {
+            self.active_cell = Some(self.start);
+            self.cursor_in_grid = true;
+        }
+    }
+
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    // While locked, clicks and the keyboard cursor only highlight cells and
+    // never change walls, endpoints or waypoints. Searches still run.
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+        if locked {
+            self.pointer_mode = PointerMode::Noop;
+            self.stroke_cell = None;
+            self.selected_endpoint = None;
+            self.history.commit_stroke();
+        }
+    }
+
+    fn apply_keyboard_action(&mut self, action: KeyboardAction) {
+        let (x, y) = match self.active_cell() {
+            Some(cell) => cell,
+            None => return,
+        };
+        match action {
+            KeyboardAction::MoveCursor(dx, dy) => {
+                let (x, y) = (x as i64 + dx, y as i64 + dy);
+                if self.grid.are_coordinates_valid(x, y) {
+                    self.active_cell = Some((x as usize, y as usize));
+                }
+            }
+            _ if self.locked => (),
+            // Same rule as painting with the mouse: endpoints never get walls.
+            KeyboardAction::ToggleWall => {
+                if (x, y) != self.start && (x, y) != self.finish {
+                    self.history.commit_stroke();
+                    self.set_wall(x, y, !self.grid.get(x, y));
+                    self.history.commit_stroke();
+                }
+            }
+            KeyboardAction::SetStart => {
+                let _ = self.set_start((x, y));
+            }
+            KeyboardAction::SetFinish => {
+                let _ = self.set_finish((x, y));
+            }
+        }
+    }
+
+    pub fn run_builtin(&mut self, algorithm: Algorithm) {
+        let options = self.search_options();
+        let result = builtin::search(algorithm, &self.grid, self.start, self.finish, &options);
+        let showing_closest_approach = result.closest_approach.is_some();
+        self.set_draw_commands(builtin::render(result, &options));
+        self.showing_closest_approach = showing_closest_approach;
+    }
+
+    // Clears all walls and puts the grid and endpoints back to how the scene
+    // was constructed. The reset is a single undoable stroke unless the grid
+    // had to be resized, which clears the history.
+    pub fn reset(&mut self) {
+        self.pointer_mode = PointerMode::Noop;
+        self.history.commit_stroke();
+        self.resize_grid(self.initial_size.0, self.initial_size.1);
+
+        let walls: Vec<_> = self
+            .grid
+            .iter()
+            .filter(|&(_, _, is_wall)| is_wall)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        for (x, y) in walls {
+            self.set_wall(x, y, false);
+        }
+        self.waypoints.clear();
+        self.move_start(self.initial_start);
+        self.move_finish(self.initial_finish);
+        self.history.commit_stroke();
+
+        self.clear_path();
+    }
+
+    // Replaces the walls with random noise as a single undoable stroke. Every
+    // call uses the next seed so repeated fills differ but stay reproducible.
+    pub fn random_fill(&mut self, probability: f32) {
+        let mut noise = Grid::new(self.grid.width(), self.grid.height());
+        noise.random_fill(probability, self.random_seed);
+        self.random_seed += 1;
+
+        self.pointer_mode = PointerMode::Noop;
+        self.history.commit_stroke();
+        for (x, y, is_wall) in noise.iter() {
+            let is_endpoint = (x, y) == self.start || (x, y) == self.finish;
+            self.set_wall(x, y, is_wall && !is_endpoint);
+        }
+        self.history.commit_stroke();
+    }
+
+    // Turns walls into open cells and back, the endpoints stay open.
+    pub fn invert_walls(&mut self) {
+        let mut inverted = self.grid.clone();
+        for (x, y, is_wall) in inverted.iter_mut() {
+            let is_endpoint = (x, y) == self.start || (x, y) == self.finish;
+            *is_wall = !*is_wall && !is_endpoint;
+        }
+
+        self.pointer_mode = PointerMode::Noop;
+        self.history.commit_stroke();
+        for (x, y, is_wall) in inverted.iter() {
+            self.set_wall(x, y, is_wall);
+        }
+        self.history.commit_stroke();
+    }
+
+    // Rotates or mirrors the whole maze, endpoints and waypoints included.
+    // The grid may change shape, so like resizing this clears the history.
+    pub fn transform_maze(&mut self, transform: GridTransform) {
+        let size = (self.grid.width(), self.grid.height());
+        self.grid = self.grid.transformed(transform);
+        self.start = transform.apply_to_cell(self.start, size);
+        self.finish = transform.apply_to_cell(self.finish, size);
+        for waypoint in &mut self.waypoints {
+            *waypoint = transform.apply_to_cell(*waypoint, size);
+        }
+
+        self.history.clear();
+        self.pointer_mode = PointerMode::Noop;
+        self.active_cell = None;
+        self.cursor_in_grid = false;
+        self.on_maze_changed();
+    }
+
+    // Draws attention to the cells with a pulsing overlay for `duration`
+    // seconds, independently of the search animation. A new call replaces the
+    // previous flash.
+    pub fn flash_cells(&mut self, cells: Vec<(usize, usize)>, duration: f32) {
+        self.flash = if duration > 0. {
+            Some(Flash {
+                cells,
+                remaining: duration,
+                elapsed: 0.,
+            })
+        } else {
+            None
+        };
+    }
+
+    fn draw_flash(&self, cx: &mut impl DrawContext) {
+        let flash = match &self.flash {
+            Some(flash) => flash,
+            None => return,
+        };
+        let color = Color::new(FLASH_COLOR.r, FLASH_COLOR.g, FLASH_COLOR.b, FLASH_COLOR.a * flash.alpha());
+        for &(x, y) in &flash.cells {
+            if x < self.grid.width() && y < self.grid.height() {
+                self.fill_cell(x, y, color, cx);
+            }
+        }
+    }
+
+    pub fn waypoints(&self) -> &[(usize, usize)] {
+        &self.waypoints
+    }
+
+    // Adds a waypoint on an open cell, or removes the one already there.
+    pub fn toggle_waypoint(&mut self, cell: (usize, usize)) {
+        if let Some(index) = self.waypoints.iter().position(|&waypoint| waypoint == cell) {
+            self.waypoints.remove(index);
+        } else if self.can_place_endpoint(cell.0, cell.1, self.start) && cell != self.finish {
+            self.waypoints.push(cell);
+        }
+        self.clear_path();
+    }
+
+    // Searches through the waypoints and the finish, either visiting all of
+    // them in placement order or stopping at the nearest one.
+    pub fn run_multi_goal(&mut self, mode: GoalMode) {
+        let mut goals = self.waypoints.clone();
+        goals.push(self.finish);
+        let commands = builtin::find_and_render_multi_goal_path(
+            Algorithm::AStar,
+            &self.grid,
+            self.start,
+            &goals,
+            mode,
+            &self.search_options(),
+        );
+        self.set_draw_commands(commands);
+    }
+
+    pub fn undo(&mut self) {
+        self.pointer_mode = PointerMode::Noop;
+        for edit in self.history.undo().unwrap_or_default() {
+            self.replay_edit(edit);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        self.pointer_mode = PointerMode::Noop;
+        for edit in self.history.redo().unwrap_or_default() {
+            self.replay_edit(edit);
+        }
+    }
+
+    pub fn resize_grid(&mut self, width: usize, height: usize) {
+        let width = width.max(MIN_GRID_SIZE).min(MAX_GRID_SIZE);
+        let height = height.max(MIN_GRID_SIZE).min(MAX_GRID_SIZE);
+        if (width, height) == (self.grid.width(), self.grid.height()) {
+            return;
+        }
+
+        self.grid.resize(width, height, false);
+        self.start = (self.start.0.min(width - 1), self.start.1.min(height - 1));
+        self.finish = (self.finish.0.min(width - 1), self.finish.1.min(height - 1));
+        if self.finish == self.start {
+            self.finish = self
+                .grid
+                .keys()
+                .find(|&cell| cell != self.start)
+                .expect("grid has at least two cells");
+        }
+        self.grid.set(self.start.0, self.start.1, false);
+        self.grid.set(self.finish.0, self.finish.1, false);
+        self.waypoints.retain(|&(x, y)| x < width && y < height);
+
+        // Recorded edits may point outside of the new bounds.
+        self.history.clear();
+        self.pointer_mode = PointerMode::Noop;
+        self.active_cell = None;
+        self.cursor_in_grid = false;
+        self.on_maze_changed();
+    }
+
+    fn fill_cell(&self, x: usize, y: usize, color: Color, cx: &mut impl DrawContext) {
+        let rect = self.get_cell_rect(x, y);
+        cx.draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+    }
+
+    fn draw_walls(&self, cx: &mut impl DrawContext) {
+        let walls: Vec<_> = self
+            .grid
+            .iter()
+            .filter(|&(_, _, is_wall)| is_wall)
+            .map(|(x, y, _)| self.get_cell_rect(x, y))
+            .collect();
+        match self.theme.wall_style {
+            WallStyle::Solid => cx.draw_rectangles(&walls, self.theme.wall),
+            WallStyle::Bordered => {
+                cx.draw_rectangles(&walls, self.theme.wall);
+                let border = WALL_BORDER_WIDTH;
+                let frames: Vec<_> = walls
+                    .iter()
+                    .flat_map(|rect| {
+                        vec![
+                            Rect::new(rect.x, rect.y, rect.w, border),
+                            Rect::new(rect.x, rect.y + rect.h - border, rect.w, border),
+                            Rect::new(rect.x, rect.y + border, border, rect.h - 2. * border),
+                            Rect::new(rect.x + rect.w - border, rect.y + border, border, rect.h - 2. * border),
+                        ]
+                    })
+                    .collect();
+                cx.draw_rectangles(&frames, darken(self.theme.wall));
+            }
+            WallStyle::Hatched => {
+                for rect in &walls {
+                    self.hatch_rect(rect, self.theme.wall, cx);
+                }
+            }
+        }
+    }
+
+    // Diagonal stripes across the rectangle, `WALL_HATCH_COUNT` per side.
+    fn hatch_rect(&self, rect: &Rect, color: Color, cx: &mut impl DrawContext) {
+        let step = rect.w / WALL_HATCH_COUNT as f32;
+        for i in 1..2 * WALL_HATCH_COUNT {
+            let offset = i as f32 * step;
+            let (x1, y1) = if offset <= rect.w {
+                (rect.x + offset, rect.y)
+            } else {
+                (rect.x + rect.w, rect.y + offset - rect.w)
+            };
+            let (x2, y2) = if offset <= rect.h {
+                (rect.x, rect.y + offset)
+            } else {
+                (rect.x + offset - rect.h, rect.y + rect.h)
+            };
+            cx.draw_line(x1, y1, x2, y2, WALL_HATCH_WIDTH, color);
+        }
+    }
+
+    fn draw_unreachable(&self, reachable: &HashSet<(usize, usize)>, cx: &mut impl DrawContext) {
+        let cells: Vec<_> = self
+            .grid
+            .iter()
+            .filter(|&(x, y, is_wall)| !is_wall && !reachable.contains(&(x, y)))
+            .map(|(x, y, _)| self.get_cell_rect(x, y))
+            .collect();
+        cx.draw_rectangles(&cells, UNREACHABLE_TINT);
+    }
+
+    // Tints every reachable cell by its distance from the start, normalized
+    // by the farthest one, using the heatmap gradient.
+    fn draw_distances(&self, distances: &Grid<Option<usize>>, cx: &mut impl DrawContext) {
+        let max_distance = distances.iter().filter_map(|(_, _, d)| d).max().unwrap_or(0).max(1);
+        let mut cells_by_step = vec![vec![]; DISTANCE_COLOR_STEPS];
+        for (x, y, distance) in distances.iter() {
+            if let Some(distance) = distance {
+                let step = distance * (DISTANCE_COLOR_STEPS - 1) / max_distance;
+                cells_by_step[step].push(self.get_cell_rect(x, y));
+            }
+        }
+        for (step, cells) in cells_by_step.iter().enumerate() {
+            let intensity = step as f32 / (DISTANCE_COLOR_STEPS - 1) as f32;
+            cx.draw_rectangles(cells, heatmap_color(intensity));
+        }
     }
 
-    fn fill_cell(&self, x: usize, y: usize, color: Color, cx: &mut DrawContext) {
+    fn draw_components(&self, components: &Grid<Option<usize>>, cx: &mut impl DrawContext) {
+        let mut cells_by_color = vec![vec![]; COMPONENT_COLORS.len()];
+        for (x, y, label) in components.iter() {
+            if let Some(label) = label {
+                cells_by_color[label % COMPONENT_COLORS.len()].push(self.get_cell_rect(x, y));
+            }
+        }
+        for (cells, &color) in cells_by_color.iter().zip(COMPONENT_COLORS.iter()) {
+            cx.draw_rectangles(cells, color);
+        }
+    }
+
+    fn mark_cell(&self, x: usize, y: usize, color: Color, cx: &mut impl DrawContext) {
         let (center_x, center_y) = self.get_cell_center(x, y);
-        cx.draw_rectangle(center_x - 50., center_y - 50., 100., 100., color);
+        cx.draw_circle(center_x, center_y, MARKER_RADIUS, color);
+    }
+
+    fn mark_endpoint(&self, (x, y): (usize, usize), color: Color, cx: &mut impl DrawContext) {
+        if self.theme.outlined_markers {
+            let (center_x, center_y) = self.get_cell_center(x, y);
+            cx.draw_circle_outline(center_x, center_y, MARKER_RADIUS, MARKER_RING_WIDTH, color);
+        } else {
+            self.mark_cell(x, y, color, cx);
+        }
     }
 
-    fn mark_cell(&self, x: usize, y: usize, color: Color, cx: &mut DrawContext) {
+    fn write_in_cell(&self, x: usize, y: usize, text: &str, color: Color, cx: &mut impl DrawContext) {
         let (center_x, center_y) = self.get_cell_center(x, y);
-        cx.draw_circle(center_x, center_y, 45., color);
+        let (width, height) = cx.measure_text(text, CELL_FONT_SIZE);
+        cx.draw_text(text, center_x - width / 2., center_y - height / 2., CELL_FONT_SIZE, color);
+    }
+
+    pub fn set_final_path_only(&mut self, final_path_only: bool) {
+        self.final_path_only = final_path_only;
+        self.animation_progress = self.animation_progress.max(self.animation_start());
+    }
+
+    // Where animations begin: right after the last `Clear` in final path only
+    // mode, so `revealed_commands` never shows anything before it.
+    fn animation_start(&self) -> f32 {
+        if !self.final_path_only {
+            return 0.;
+        }
+        self.draw_commands
+            .iter()
+            .rposition(|cmd| matches!(cmd, DrawCommand::Clear))
+            .map_or(0., |i| (i + 1) as f32)
     }
 
-    fn draw_animation(&self, cx: &mut DrawContext) {
+    // Commands revealed by the animation so far, starting after the last
+    // `Clear` among them.
+    fn revealed_commands(&self) -> &[DrawCommand] {
         let end = self
             .draw_commands
             .len()
@@ -102,9 +982,37 @@ impl PathtfindScene {
             .rfind(|(_, cmd)| matches!(cmd, DrawCommand::Clear))
             .map(|(i, _)| i + 1)
             .unwrap_or(0);
-        for cmd in &self.draw_commands[start..end] {
+        &self.draw_commands[start..end]
+    }
+
+    fn draw_explored_count(&self, cx: &mut impl DrawContext) {
+        let explored = self
+            .revealed_commands()
+            .iter()
+            .filter(|cmd| matches!(cmd, DrawCommand::AddShape(Shape::Square { .. })))
+            .count();
+        let text = format!("Explored: {}", explored);
+        self.draw_status(StatusLine::Explored, &text, colors::BLACK, cx);
+    }
+
+    fn draw_animation(&self, cx: &mut impl DrawContext) {
+        let visible = self.revealed_commands();
+        let mut latest_annotations = HashMap::new();
+        for (i, cmd) in visible.iter().enumerate() {
+            if let DrawCommand::AddShape(Shape::Annotation { x, y, .. }) = cmd {
+                latest_annotations.insert((*x, *y), i);
+            }
+        }
+
+        for (i, cmd) in visible.iter().enumerate() {
             match cmd {
-                &DrawCommand::AddShape(Shape::Square { x, y, color }) => {
+                &DrawCommand::AddShape(Shape::Square {
+                    x,
+                    y,
+                    color,
+                    intensity,
+                }) => {
+                    let color = intensity.map_or(color, heatmap_color);
                     self.fill_cell(x, y, color, cx);
                 }
                 &DrawCommand::AddShape(Shape::Line {
@@ -128,19 +1036,49 @@ impl PathtfindScene {
                         cx.draw_line(x1, y1, x2, y2, width, color);
                     }
                 }
-                &DrawCommand::Clear => unreachable!(),
+                &DrawCommand::AddShape(Shape::Circle { x, y, r, color }) => {
+                    let (center_x, center_y) = self.get_cell_center(x, y);
+                    cx.draw_circle(center_x, center_y, r, color);
+                }
+                &DrawCommand::AddShape(Shape::Text {
+                    x,
+                    y,
+                    ref text,
+                    color,
+                }) => {
+                    self.write_in_cell(x, y, text, color, cx);
+                }
+                &DrawCommand::AddShape(Shape::Annotation { x, y, ref text }) => {
+                    if latest_annotations.get(&(x, y)) == Some(&i) {
+                        self.write_in_cell(x, y, text, colors::BLACK, cx);
+                    }
+                }
+                // `revealed_commands` starts after the last clear, but a stray
+                // one must not take the frame down.
+                &DrawCommand::Clear => (),
             }
         }
     }
 
-    fn draw_highlight(&self, cx: &mut DrawContext) {
+    fn draw_highlight(&self, cx: &mut impl DrawContext) {
         let highlight_color = Color::new(1., 1., 1., 0.25);
-        let maybe_cell = if self.active_cell == Some(self.start)
-            || matches!(self.pointer_mode, PointerMode::SetStart)
+        if let (PointerMode::FillRect { origin, .. }, Some(corner)) = (self.pointer_mode, self.active_cell) {
+            let rects: Vec<_> = rect_cells(origin, corner)
+                .map(|(x, y)| self.get_cell_rect(x, y))
+                .collect();
+            cx.draw_rectangles(&rects, highlight_color);
+            return;
+        }
+        let maybe_cell = if self.keyboard_mode {
+            self.active_cell()
+        } else if self.active_cell == Some(self.start)
+            || matches!(self.pointer_mode, PointerMode::SetStart { .. })
+            || self.selected_endpoint == Some(Endpoint::Start)
         {
             Some(self.start)
         } else if self.active_cell == Some(self.finish)
-            || matches!(self.pointer_mode, PointerMode::SetFinish)
+            || matches!(self.pointer_mode, PointerMode::SetFinish { .. })
+            || self.selected_endpoint == Some(Endpoint::Finish)
         {
             Some(self.finish)
         } else {
@@ -155,102 +1093,671 @@ impl PathtfindScene {
         }
     }
 
-    fn draw_bars(&self, color: Color, cx: &mut DrawContext) {
-        let config = self.config();
-        for x in 0..self.grid.width() + 1 {
-            cx.draw_rectangle(x as f32 * 100., 0., 5., config.height, color);
-        }
-        for y in 0..self.grid.height() + 1 {
-            cx.draw_rectangle(0., y as f32 * 100., config.width, 5., color);
-        }
-    }
+    // Labels the hovered cell with its coordinates. The label sits to the top
+    // right of the cell and flips to the other side near the scene edges.
+    fn draw_tooltip(&self, cx: &mut impl DrawContext) {
+        let (x, y) = match self.active_cell {
+            Some(cell) => cell,
+            None => return,
+        };
+        let text = format!("({}, {})", x, y);
+        let (text_width, text_height) = cx.measure_text(&text, TOOLTIP_FONT_SIZE);
+        let width = text_width + 2. * TOOLTIP_PADDING;
+        let height = text_height + 2. * TOOLTIP_PADDING;
 
-    fn get_cell_coordinates(&self, scene_x: f32, scene_y: f32) -> (i32, i32) {
-        (
-            ((scene_x + 2.5) / 100.) as i32,
-            ((scene_y + 2.5) / 100.) as i32,
-        )
+        let (grid_width, grid_height) = self.grid_extent();
+        let rect = self.get_cell_rect(x, y);
+        let left = if rect.x + rect.w + width <= grid_width {
+            rect.x + rect.w
+        } else {
+            (rect.x - width).max(0.)
+        };
+        let bottom = if rect.y + rect.h + height <= grid_height {
+            rect.y + rect.h
+        } else {
+            (rect.y - height).max(0.)
+        };
+
+        cx.draw_rectangle(left, bottom, width, height, TOOLTIP_BACKGROUND);
+        cx.draw_text(
+            &text,
+            left + TOOLTIP_PADDING,
+            bottom + TOOLTIP_PADDING,
+            TOOLTIP_FONT_SIZE,
+            colors::BLACK,
+        );
     }
 
-    fn get_cell_center(&self, cell_x: usize, cell_y: usize) -> (f32, f32) {
-        (52.5 + cell_x as f32 * 100., 52.5 + cell_y as f32 * 100.)
+    // Waypoints are smaller finish markers numbered in visiting order.
+    fn draw_waypoints(&self, cx: &mut impl DrawContext) {
+        for (i, &(x, y)) in self.waypoints.iter().enumerate() {
+            let (center_x, center_y) = self.get_cell_center(x, y);
+            cx.draw_circle(center_x, center_y, WAYPOINT_RADIUS, self.theme.finish);
+            self.write_in_cell(x, y, &(i + 1).to_string(), colors::WHITE, cx);
+        }
+    }
+
+    fn draw_legend(&self, cx: &mut impl DrawContext) {
+        let entries = [
+            ("Start", self.theme.start),
+            ("Finish", self.theme.finish),
+            ("Wall", self.theme.wall),
+            ("Path", self.theme.path),
+            ("Visited", builtin::EXPANSION_COLOR),
+            ("Visited from finish", builtin::BACKWARD_EXPANSION_COLOR),
+            ("Closest approach", builtin::PARTIAL_PATH_COLOR),
+            ("Meeting point", builtin::MEETING_COLOR),
+        ];
+        let (grid_width, grid_height) = self.grid_extent();
+        let row_height = (grid_height / (entries.len() + 1) as f32).min(CELL_SIZE);
+        let swatch_size = 0.6 * row_height;
+        let font_size = 0.4 * row_height;
+        let left = grid_width + LEGEND_MARGIN;
+
+        for (i, &(label, color)) in entries.iter().enumerate() {
+            let bottom = grid_height - (i + 1) as f32 * row_height;
+            cx.draw_rectangle(left, bottom, swatch_size, swatch_size, color);
+            let (_, text_height) = cx.measure_text(label, font_size);
+            cx.draw_text(
+                label,
+                left + swatch_size + LEGEND_LABEL_GAP,
+                bottom + (swatch_size - text_height) / 2.,
+                font_size,
+                colors::BLACK,
+            );
+        }
+    }
+
+    fn draw_solvable(&self, solvable: bool, cx: &mut impl DrawContext) {
+        let (text, color) = if solvable {
+            ("Solvable", colors::DARKGREEN)
+        } else {
+            ("No path", colors::RED)
+        };
+        self.draw_status(StatusLine::Solvable, text, color, cx);
+    }
+
+    fn draw_fps(&self, cx: &mut impl DrawContext) {
+        let text = format!(
+            "{:.0} FPS ({:.1} ms)",
+            self.frame_timer.fps(),
+            self.frame_timer.frame_time() * 1000.
+        );
+        self.draw_status(StatusLine::Fps, &text, colors::BLACK, cx);
+    }
+
+    fn draw_status(&self, line: StatusLine, text: &str, color: Color, cx: &mut impl DrawContext) {
+        // `y` points up, so the first line sits at the bottom.
+        let y = STATUS_MARGIN + line as usize as f32 * STATUS_LINE_HEIGHT;
+        cx.draw_text(text, STATUS_MARGIN, y, STATUS_FONT_SIZE, color);
+    }
+
+    fn draw_bars(&self, color: Color, cx: &mut impl DrawContext) {
+        let (grid_width, grid_height) = self.grid_extent();
+        let offset = GRID_MARGIN - self.bar_width / 2.;
+        for x in 0..self.grid.width() + 1 {
+            let bar_x = x as f32 * CELL_SIZE + offset;
+            cx.draw_rectangle(bar_x, offset, self.bar_width, grid_height - 2. * offset, color);
+        }
+        for y in 0..self.grid.height() + 1 {
+            let bar_y = y as f32 * CELL_SIZE + offset;
+            cx.draw_rectangle(offset, bar_y, grid_width - 2. * offset, self.bar_width, color);
+        }
+    }
+
+    // Size of the grid with its bars in scene units, without side panels.
+    fn grid_extent(&self) -> (f32, f32) {
+        (
+            2. * GRID_MARGIN + self.grid.width() as f32 * CELL_SIZE,
+            2. * GRID_MARGIN + self.grid.height() as f32 * CELL_SIZE,
+        )
+    }
+
+    // A cell owns the square between the lines around it, so every point maps
+    // back to the cell whose center is closest along each axis.
+    fn get_cell_coordinates(&self, scene_x: f32, scene_y: f32) -> (i32, i32) {
+        (
+            ((scene_x - GRID_MARGIN) / CELL_SIZE).floor() as i32,
+            ((scene_y - GRID_MARGIN) / CELL_SIZE).floor() as i32,
+        )
+    }
+
+    fn get_cell_rect(&self, cell_x: usize, cell_y: usize) -> Rect {
+        let (center_x, center_y) = self.get_cell_center(cell_x, cell_y);
+        let half = CELL_SIZE / 2.;
+        Rect::new(center_x - half, center_y - half, CELL_SIZE, CELL_SIZE)
+    }
+
+    fn get_cell_center(&self, cell_x: usize, cell_y: usize) -> (f32, f32) {
+        let center = |cell: usize| GRID_MARGIN + (cell as f32 + 0.5) * CELL_SIZE;
+        (center(cell_x), center(cell_y))
+    }
+
+    fn check_endpoint(&self, cell: (usize, usize), other: (usize, usize)) -> Result<(), &'static str> {
+        check_endpoint_cell(&self.grid, cell, other)?;
+        if self.waypoints.contains(&cell) {
+            return Err("cell is a waypoint");
+        }
+        Ok(())
+    }
+
+    fn can_place_endpoint(&self, x: usize, y: usize, other: (usize, usize)) -> bool {
+        self.grid.try_get(x as i64, y as i64) == Some(false)
+            && (x, y) != other
+            && !self.waypoints.contains(&(x, y))
+    }
+
+    fn clear_path(&mut self) {
+        self.draw_commands.clear();
+        self.animation_progress = 0.;
+        self.showing_closest_approach = false;
+    }
+
+    fn on_maze_changed(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.cancel_background_search();
+
+        // A waypoint painted over by a wall is gone, it doesn't come back on undo.
+        let grid = &self.grid;
+        self.waypoints.retain(|&(x, y)| !grid.get(x, y));
+        self.reachable = None;
+        self.components = None;
+        self.distances = None;
+        self.clear_path();
+        if self.live_mode {
+            self.live_search_timer = Some(LIVE_SEARCH_DELAY);
+        }
+        self.schedule_solvable_check();
+    }
+
+    fn set_show_solvable(&mut self, show_solvable: bool) {
+        self.show_solvable = show_solvable;
+        self.solvable = None;
+        self.solvable_timer = if show_solvable { Some(0.) } else { None };
+    }
+
+    fn schedule_solvable_check(&mut self) {
+        if self.show_solvable {
+            self.solvable_timer = Some(SOLVABLE_CHECK_DELAY);
+        }
+    }
+
+    // Counts down the debounce timer and flood fills from the start once the
+    // edits settle, with the same moves the built-in searches use.
+    fn update_solvable(&mut self, delta: f32) {
+        let remaining = match self.solvable_timer {
+            Some(remaining) => remaining - delta,
+            None => return,
+        };
+        if remaining > 0. {
+            self.solvable_timer = Some(remaining);
+            return;
+        }
+        self.solvable_timer = None;
+
+        let distances = match self.distances.take() {
+            Some(distances) => distances,
+            None => builtin::distance_map(&self.grid, self.start, self.connectivity),
+        };
+        self.solvable = Some(distances.get(self.finish.0, self.finish.1).is_some());
+        self.distances = Some(distances);
+    }
+
+    // Counts down the debounce timer and re-runs the search once edits settle
+    // and no stroke is in progress.
+    fn update_live_search(&mut self, delta: f32) {
+        let remaining = match self.live_search_timer {
+            Some(remaining) => remaining - delta,
+            None => return,
+        };
+        if remaining > 0. || !matches!(self.pointer_mode, PointerMode::Noop) {
+            self.live_search_timer = Some(remaining.max(0.));
+            return;
+        }
+        self.live_search_timer = None;
+
+        let options = self.search_options();
+        match self.live_searcher.as_mut() {
+            Some(searcher) => {
+                if let Some(commands) = searcher(&self.grid, self.start, self.finish, &options) {
+                    self.set_draw_commands(commands);
+                }
+            }
+            None => self.run_builtin(Algorithm::AStar),
+        }
+    }
+
+    fn set_wall(&mut self, x: usize, y: usize, value: bool) {
+        let before = match self.grid.get_checked(x, y) {
+            Ok(before) => before,
+            Err(_) => return,
+        };
+        if before != value {
+            self.apply_edit(Edit::Wall {
+                x,
+                y,
+                before,
+                after: value,
+            });
+        }
+    }
+
+    fn move_start(&mut self, cell: (usize, usize)) {
+        if cell != self.start {
+            self.apply_edit(Edit::Start {
+                before: self.start,
+                after: cell,
+            });
+        }
+    }
+
+    fn move_finish(&mut self, cell: (usize, usize)) {
+        if cell != self.finish {
+            self.apply_edit(Edit::Finish {
+                before: self.finish,
+                after: cell,
+            });
+        }
+    }
+
+    fn apply_edit(&mut self, edit: Edit) {
+        self.history.record(edit);
+        self.replay_edit(edit);
+    }
+
+    fn replay_edit(&mut self, edit: Edit) {
+        match edit {
+            Edit::Wall { x, y, after, .. } => {
+                let _ = self.grid.set_checked(x, y, after);
+            }
+            Edit::Start { after, .. } => self.start = after,
+            Edit::Finish { after, .. } => self.finish = after,
+        }
+        self.on_maze_changed();
     }
 
     fn apply_pointer_action(&mut self, x: usize, y: usize) {
+        if self.locked {
+            return;
+        }
         let is_special = (x, y) == self.start || (x, y) == self.finish;
-        let is_wall = self.grid.get(x, y);
-        let old_commands = std::mem::replace(&mut self.draw_commands, vec![]);
         match self.pointer_mode {
-            PointerMode::SetWall if !is_special => self.grid.set(x, y, true),
-            PointerMode::SetStart if !is_special && !is_wall => self.start = (x, y),
-            PointerMode::SetFinish if !is_special && !is_wall => self.finish = (x, y),
-            PointerMode::EraseWall => self.grid.set(x, y, false),
-            _ => self.draw_commands = old_commands,
+            PointerMode::SetWall if !is_special => self.set_wall(x, y, true),
+            PointerMode::EraseWall => self.set_wall(x, y, false),
+            PointerMode::SetStart { origin } => {
+                if self.can_place_endpoint(x, y, self.finish) {
+                    self.move_start((x, y));
+                } else {
+                    self.move_start(origin);
+                }
+            }
+            PointerMode::SetFinish { origin } => {
+                if self.can_place_endpoint(x, y, self.start) {
+                    self.move_finish((x, y));
+                } else {
+                    self.move_finish(origin);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // The cursor can jump several cells between two mouse samples, so wall
+    // strokes are continued along a straight line from the previous sample.
+    fn apply_stroke(&mut self, cell: (usize, usize)) {
+        match (self.pointer_mode, self.stroke_cell) {
+            (PointerMode::SetWall, Some(previous)) | (PointerMode::EraseWall, Some(previous)) => {
+                let cells: Vec<_> = self.grid.line(previous, cell).skip(1).collect();
+                for (x, y) in cells {
+                    self.apply_pointer_action(x, y);
+                }
+            }
+            _ => self.apply_pointer_action(cell.0, cell.1),
+        }
+        if !matches!(self.pointer_mode, PointerMode::Noop) {
+            self.stroke_cell = Some(cell);
+        }
+    }
+
+    // Sets or erases every cell of the rectangle spanned by the two corners,
+    // leaving the endpoints open.
+    fn fill_rect(&mut self, origin: (usize, usize), corner: (usize, usize), is_wall: bool) {
+        for (x, y) in rect_cells(origin, corner) {
+            if !is_wall || ((x, y) != self.start && (x, y) != self.finish) {
+                self.set_wall(x, y, is_wall);
+            }
+        }
+    }
+
+    // Cell one step from `cell` in the given direction, if that is a valid
+    // place for an endpoint whose counterpart is at `other`.
+    fn nudge_target(
+        &self,
+        cell: (usize, usize),
+        (dx, dy): (i64, i64),
+        other: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (x, y) = (cell.0 as i64 + dx, cell.1 as i64 + dy);
+        if x < 0 || y < 0 || !self.can_place_endpoint(x as usize, y as usize, other) {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    // Moves the endpoint selected by clicking it one cell, as its own
+    // undoable step.
+    fn nudge_selected_endpoint(&mut self, delta: (i64, i64)) {
+        match self.selected_endpoint {
+            Some(Endpoint::Start) => {
+                if let Some(cell) = self.nudge_target(self.start, delta, self.finish) {
+                    self.move_start(cell);
+                }
+            }
+            Some(Endpoint::Finish) => {
+                if let Some(cell) = self.nudge_target(self.finish, delta, self.start) {
+                    self.move_finish(cell);
+                }
+            }
+            None => return,
+        }
+        self.history.commit_stroke();
+    }
+
+    fn snap_back_endpoint(&mut self) {
+        match self.pointer_mode {
+            PointerMode::SetStart { origin } => self.move_start(origin),
+            PointerMode::SetFinish { origin } => self.move_finish(origin),
+            _ => (),
         }
     }
 }
 
+// Same hue at 60% brightness, for outlines drawn over the color itself.
+fn darken(color: Color) -> Color {
+    Color::new(0.6 * color.r, 0.6 * color.g, 0.6 * color.b, color.a)
+}
+
+// The default heatmap gradient: blue for 0 (early), through yellow, to red for
+// 1 (late). Intensities outside of [0, 1] are clamped.
+pub fn heatmap_color(intensity: f32) -> Color {
+    const COOL: (f32, f32, f32) = (0.2, 0.4, 1.);
+    const MIDDLE: (f32, f32, f32) = (1., 0.9, 0.2);
+    const WARM: (f32, f32, f32) = (1., 0.2, 0.1);
+    let t = if intensity.is_nan() { 0. } else { intensity.max(0.).min(1.) };
+    let (from, to, t) = if t < 0.5 {
+        (COOL, MIDDLE, 2. * t)
+    } else {
+        (MIDDLE, WARM, 2. * t - 1.)
+    };
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    Color::new(lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2), 0.6)
+}
+
+// Cells of the rectangle with the given opposite corners, in any order.
+fn rect_cells(a: (usize, usize), b: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    let (x0, x1) = (a.0.min(b.0), a.0.max(b.0));
+    let (y0, y1) = (a.1.min(b.1), a.1.max(b.1));
+    (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+}
+
 impl Scene for PathtfindScene {
     fn config(&self) -> SceneConfig {
-        let width = self.grid.width() * 100;
-        let height = self.grid.height() * 100;
+        let (width, height) = self.grid_extent();
+        let legend_width = if self.show_legend { LEGEND_WIDTH } else { 0. };
         SceneConfig {
-            width: 5. + width as f32,
-            height: 5. + height as f32,
-            bgcolor: colors::LIGHTGRAY,
+            width: width + legend_width,
+            height,
+            bgcolor: self.theme.background,
         }
     }
 
+    fn on_enter(&mut self) {
+        self.entered = true;
+        self.upload_background();
+    }
+
+    fn animation_length(&self) -> usize {
+        self.draw_commands.len()
+    }
+
+    // Steps before `animation_start` show the same frame as the start, so
+    // final path only mode hides the exploration when seeking too.
+    fn seek_animation(&mut self, step: usize) {
+        self.animation_progress = (step.min(self.draw_commands.len()) as f32).max(self.animation_start());
+    }
+
     fn update(&mut self, delta: f32) {
+        self.frame_timer.record(delta);
+        self.update_live_search(delta);
+        self.update_solvable(delta);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll_background_search();
+        if let Some(flash) = &mut self.flash {
+            if !flash.advance(delta) {
+                self.flash = None;
+            }
+        }
         if self.animation_progress < 0. {
-            self.animation_progress = 0.;
+            self.animation_progress = self.animation_start();
         } else if self.animation_progress < self.draw_commands.len() as f32 {
-            self.animation_progress += 100. * delta;
+            self.animation_progress += ANIMATION_SPEED * delta;
         }
     }
 
-    fn draw(&mut self, cx: &mut DrawContext) {
-        for (x, y, value) in self.grid.iter() {
-            if value {
-                self.fill_cell(x, y, colors::GRAY, cx);
-            }
+    fn draw(&mut self, cx: &mut impl DrawContext) {
+        if let Some(texture) = self.background {
+            let (width, height) = self.grid_extent();
+            cx.draw_texture(texture, 0., 0., width, height);
         }
 
-        self.draw_bars(colors::WHITE, cx);
+        if self.show_distances {
+            let distances = match self.distances.take() {
+                Some(distances) => distances,
+                None => builtin::distance_map(&self.grid, self.start, self.connectivity),
+            };
+            self.draw_distances(&distances, cx);
+            self.distances = Some(distances);
+        } else if self.show_components {
+            let components = match self.components.take() {
+                Some(components) => components,
+                None => self.grid.components().0,
+            };
+            self.draw_components(&components, cx);
+            self.components = Some(components);
+        } else {
+            let reachable = match self.reachable.take() {
+                Some(reachable) => reachable,
+                None => self.grid.reachable_from(self.start),
+            };
+            self.draw_unreachable(&reachable, cx);
+            self.reachable = Some(reachable);
+        }
+
+        self.draw_walls(cx);
+        if self.show_bars {
+            self.draw_bars(self.theme.bars, cx);
+        }
         self.draw_animation(cx);
 
-        self.mark_cell(self.start.0, self.start.1, colors::DARKGREEN, cx);
-        self.mark_cell(self.finish.0, self.finish.1, colors::DARKBLUE, cx);
+        self.mark_endpoint(self.start, self.theme.start, cx);
+        self.mark_endpoint(self.finish, self.theme.finish, cx);
+        self.draw_waypoints(cx);
+        self.draw_flash(cx);
 
         self.draw_highlight(cx);
+
+        if self.show_tooltip {
+            self.draw_tooltip(cx);
+        }
+        if self.show_legend {
+            self.draw_legend(cx);
+        }
+        if self.show_fps {
+            self.draw_fps(cx);
+        }
+        if self.show_explored_count && !self.draw_commands.is_empty() {
+            self.draw_explored_count(cx);
+        }
+        if let (true, Some(solvable)) = (self.show_solvable, self.solvable) {
+            self.draw_solvable(solvable, cx);
+        }
+        if self.locked {
+            self.draw_status(StatusLine::Locked, "Locked", colors::BLACK, cx);
+        }
+        if self.showing_closest_approach {
+            self.draw_status(
+                StatusLine::ClosestApproach,
+                "Goal unreachable; showing closest approach",
+                colors::RED,
+                cx,
+            );
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.draw_search_indicator(cx);
     }
 
     fn handle_event(&mut self, event: Event) {
+        if let Event::KeyDown { key } = event {
+            if let Some(action) = KeyboardAction::from_key(key).filter(|_| self.keyboard_mode) {
+                self.apply_keyboard_action(action);
+                return;
+            }
+        }
+
         match event {
+            // Left button paints walls and drags the endpoints, right button
+            // erases. Holding shift fills a rectangle instead of a stroke.
             Event::MouseDown {
-                button: MouseButton::Left,
+                button: button @ MouseButton::Left,
+                x: mouse_x,
+                y: mouse_y,
+            }
+            | Event::MouseDown {
+                button: button @ MouseButton::Right,
                 x: mouse_x,
                 y: mouse_y,
             } => {
                 let (x, y) = self.get_cell_coordinates(mouse_x, mouse_y);
-                self.pointer_mode = if (x as usize, y as usize) == self.start {
-                    PointerMode::SetStart
-                } else if (x as usize, y as usize) == self.finish {
-                    PointerMode::SetFinish
-                } else {
-                    match self.grid.try_get(x, y) {
-                        Some(true) => PointerMode::EraseWall,
-                        Some(false) => PointerMode::SetWall,
-                        None => PointerMode::Noop,
+                let is_painting = button == MouseButton::Left;
+                self.pointer_mode = if self.locked || !self.grid.are_coordinates_valid(x, y) {
+                    PointerMode::Noop
+                } else if is_painting && (x as usize, y as usize) == self.start {
+                    PointerMode::SetStart { origin: self.start }
+                } else if is_painting && (x as usize, y as usize) == self.finish {
+                    PointerMode::SetFinish { origin: self.finish }
+                } else if self.shift_held {
+                    PointerMode::FillRect {
+                        origin: (x as usize, y as usize),
+                        is_wall: is_painting,
                     }
+                } else if is_painting {
+                    PointerMode::SetWall
+                } else {
+                    PointerMode::EraseWall
+                };
+                self.selected_endpoint = match self.pointer_mode {
+                    PointerMode::SetStart { .. } => Some(Endpoint::Start),
+                    PointerMode::SetFinish { .. } => Some(Endpoint::Finish),
+                    _ => None,
                 };
                 self.apply_pointer_action(x as usize, y as usize);
+                self.stroke_cell = if self.grid.are_coordinates_valid(x, y) {
+                    Some((x as usize, y as usize))
+                } else {
+                    None
+                };
+            }
+            Event::MouseDown {
+                button: MouseButton::Middle,
+                x: mouse_x,
+                y: mouse_y,
+            } => {
+                let (x, y) = self.get_cell_coordinates(mouse_x, mouse_y);
+                if !self.locked && self.grid.are_coordinates_valid(x, y) {
+                    self.toggle_waypoint((x as usize, y as usize));
+                }
             }
             Event::MouseUp {
                 button: MouseButton::Left,
                 ..
+            }
+            | Event::MouseUp {
+                button: MouseButton::Right,
+                ..
             } => {
+                if let PointerMode::FillRect { origin, is_wall } = self.pointer_mode {
+                    if let Some(corner) = self.active_cell {
+                        self.fill_rect(origin, corner, is_wall);
+                    }
+                }
                 self.pointer_mode = PointerMode::Noop;
+                self.stroke_cell = None;
+                self.history.commit_stroke();
+            }
+            Event::KeyDown {
+                key: Key::LeftShift,
+            }
+            | Event::KeyDown {
+                key: Key::RightShift,
+            } => self.shift_held = true,
+            Event::KeyUp {
+                key: Key::LeftShift,
+            }
+            | Event::KeyUp {
+                key: Key::RightShift,
+            } => self.shift_held = false,
+            Event::KeyDown { key: Key::Up } => self.nudge_selected_endpoint((0, 1)),
+            Event::KeyDown { key: Key::Down } => self.nudge_selected_endpoint((0, -1)),
+            Event::KeyDown { key: Key::Left } => self.nudge_selected_endpoint((-1, 0)),
+            Event::KeyDown { key: Key::Right } => self.nudge_selected_endpoint((1, 0)),
+            Event::KeyDown { key: Key::Z } => self.undo(),
+            Event::KeyDown { key: Key::Y } => self.redo(),
+            Event::KeyDown { key: Key::Key1 } => self.run_builtin(Algorithm::Bfs),
+            Event::KeyDown { key: Key::Key2 } => self.run_builtin(Algorithm::AStar),
+            Event::KeyDown { key: Key::Key3 } => self.run_builtin(Algorithm::BidirectionalBfs),
+            Event::KeyDown { key: Key::Key4 } => self.run_multi_goal(GoalMode::Nearest),
+            Event::KeyDown { key: Key::Key5 } => self.run_multi_goal(GoalMode::Ordered),
+            Event::KeyDown { key: Key::R } => self.reset(),
+            Event::KeyDown { key: Key::Enter } => self.replay(),
+            Event::KeyDown { key: Key::O } => self.set_final_path_only(!self.final_path_only),
+            Event::KeyDown { key: Key::N } => self.random_fill(RANDOM_FILL_PROBABILITY),
+            Event::KeyDown { key: Key::I } => self.invert_walls(),
+            Event::KeyDown { key: Key::Comma } => self.transform_maze(GridTransform::Rotate90),
+            Event::KeyDown { key: Key::Slash } => self.transform_maze(GridTransform::Transpose),
+            Event::KeyDown { key: Key::Period } => self.transform_maze(if self.shift_held {
+                GridTransform::FlipVertical
+            } else {
+                GridTransform::FlipHorizontal
+            }),
+            Event::KeyDown { key: Key::K } => self.set_keyboard_mode(!self.keyboard_mode),
+            Event::KeyDown { key: Key::L } => self.set_locked(!self.locked),
+            #[cfg(not(target_arch = "wasm32"))]
+            Event::KeyDown { key: Key::X } => self.toggle_background_search(),
+            Event::KeyDown { key: Key::V } => self.set_live_mode(!self.live_mode),
+            Event::KeyDown { key: Key::C } => self.show_components = !self.show_components,
+            Event::KeyDown { key: Key::F } => self.show_fps = !self.show_fps,
+            Event::KeyDown { key: Key::U } => self.show_explored_count = !self.show_explored_count,
+            Event::KeyDown { key: Key::J } => self.set_show_solvable(!self.show_solvable),
+            Event::KeyDown { key: Key::B } => self.show_bars = !self.show_bars,
+            Event::KeyDown { key: Key::M } => self.theme.wall_style = self.theme.wall_style.next(),
+            Event::KeyDown { key: Key::T } => self.show_tooltip = !self.show_tooltip,
+            Event::KeyDown { key: Key::H } => self.show_legend = !self.show_legend,
+            Event::KeyDown { key: Key::G } => self.show_distances = !self.show_distances,
+            Event::KeyDown { key: Key::W } => self.heatmap = !self.heatmap,
+            Event::KeyDown { key: Key::Q } => self.gradient_path = !self.gradient_path,
+            Event::KeyDown { key: Key::A } => self.closest_approach = !self.closest_approach,
+            Event::KeyDown { key: Key::D } => self.set_connectivity(self.connectivity.next()),
+            Event::KeyDown { key: Key::LeftBracket } => {
+                self.resize_grid(self.grid.width() - 1, self.grid.height());
+            }
+            Event::KeyDown { key: Key::RightBracket } => {
+                self.resize_grid(self.grid.width() + 1, self.grid.height());
+            }
+            Event::KeyDown { key: Key::Minus } => {
+                self.resize_grid(self.grid.width(), self.grid.height() - 1);
+            }
+            Event::KeyDown { key: Key::Equal } => {
+                self.resize_grid(self.grid.width(), self.grid.height() + 1);
             }
             Event::MouseMoved {
                 x: mouse_x,
@@ -258,11 +1765,833 @@ impl Scene for PathtfindScene {
             } => {
                 let (x, y) = self.get_cell_coordinates(mouse_x, mouse_y);
                 if self.grid.are_coordinates_valid(x, y) {
-                    self.active_cell = Some((x as usize, y as usize));
-                    self.apply_pointer_action(x as usize, y as usize);
+                    let cell = (x as usize, y as usize);
+                    self.active_cell = Some(cell);
+                    self.cursor_in_grid = true;
+                    self.apply_stroke(cell);
+                } else {
+                    self.cursor_in_grid = false;
+                    self.stroke_cell = None;
+                    self.snap_back_endpoint();
                 }
             }
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{DrawOp, RecordingDrawContext, SceneDriver};
+
+    // An open grid with the start in the bottom left corner and the finish in
+    // the top right one.
+    fn open_scene(width: usize, height: usize) -> PathtfindScene {
+        PathtfindScene::new(Grid::new(width, height), (0, 0), (width - 1, height - 1))
+    }
+
+    #[test]
+    fn try_new_rejects_bad_endpoints() {
+        let mut grid = Grid::new(3, 3);
+        grid.set(1, 1, true);
+        let attempt = |start, finish| PathtfindScene::try_new(grid.clone(), start, finish).err();
+
+        assert_eq!(attempt((0, 0), (2, 2)), None);
+        assert_eq!(attempt((1, 2), (1, 2)), Some("start and finish must be different cells"));
+        assert_eq!(attempt((1, 1), (2, 2)), Some("cell is a wall"));
+        assert_eq!(attempt((0, 0), (3, 0)), Some("cell is outside of the grid"));
+    }
+
+    fn left_drag(scene: &PathtfindScene, cells: &[(usize, usize)]) -> Vec<Event> {
+        let points: Vec<_> = cells.iter().map(|&(x, y)| scene.get_cell_center(x, y)).collect();
+        let (first, last) = (points[0], points[points.len() - 1]);
+        let mut events = vec![Event::MouseDown {
+            button: MouseButton::Left,
+            x: first.0,
+            y: first.1,
+        }];
+        events.extend(points[1..].iter().map(|&(x, y)| Event::MouseMoved { x, y }));
+        events.push(Event::MouseUp {
+            button: MouseButton::Left,
+            x: last.0,
+            y: last.1,
+        });
+        events
+    }
+
+    fn rects_of_color(ops: &[DrawOp], color: Color) -> Vec<Rect> {
+        ops.iter()
+            .filter_map(|op| match *op {
+                DrawOp::Rectangle { x, y, w, h, color: c } if c == color => Some(Rect::new(x, y, w, h)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // A color nothing else in the scene is drawn with.
+    const MARKER: Color = Color::new(0.1, 0.2, 0.3, 1.);
+
+    fn marker_square(x: usize) -> DrawCommand {
+        DrawCommand::AddShape(Shape::Square {
+            x,
+            y: 1,
+            color: MARKER,
+            intensity: None,
+        })
+    }
+
+    #[test]
+    fn driver_paints_walls_with_a_mouse_drag() {
+        let scene = open_scene(4, 2);
+        let events = left_drag(&scene, &[(1, 0), (2, 0)]);
+        let mut driver = SceneDriver::new(scene);
+        let ops = driver.play(events);
+
+        let grid = driver.scene.grid();
+        assert!(grid.get(1, 0) && grid.get(2, 0));
+        assert_eq!(grid.count_true(), 2);
+
+        let wall_rects = rects_of_color(&ops, driver.scene.theme().wall);
+        for &(x, y) in &[(1, 0), (2, 0)] {
+            assert!(wall_rects.contains(&driver.scene.get_cell_rect(x, y)));
+        }
+        assert!(!wall_rects.contains(&driver.scene.get_cell_rect(3, 0)));
+    }
+
+    #[test]
+    fn resize_grid_clamps_endpoints_into_the_new_bounds() {
+        let mut scene = open_scene(5, 5);
+        scene.resize_grid(3, 2);
+
+        assert_eq!((scene.grid().width(), scene.grid().height()), (3, 2));
+        assert_eq!(scene.start(), (0, 0));
+        assert_eq!(scene.finish(), (2, 1));
+    }
+
+    #[test]
+    fn resize_grid_keeps_walls_in_the_overlap() {
+        let mut scene = open_scene(3, 3);
+        scene.grid.set(1, 1, true);
+        scene.resize_grid(6, 4);
+
+        assert!(scene.grid().get(1, 1));
+        assert_eq!(scene.grid().count_true(), 1);
+        assert_eq!(scene.finish(), (2, 2));
+    }
+
+    #[test]
+    fn resize_grid_moves_the_finish_off_the_start() {
+        let mut scene = PathtfindScene::new(Grid::new(4, 4), (1, 1), (3, 3));
+        scene.resize_grid(2, 2);
+        assert_eq!(scene.start(), (1, 1));
+        assert_ne!(scene.finish(), scene.start());
+    }
+
+    #[test]
+    fn d_key_cycles_connectivity_for_the_builtin_search() {
+        let mut scene = open_scene(4, 4);
+        assert_eq!(scene.connectivity(), Connectivity::Four);
+        scene.handle_event(Event::KeyDown { key: Key::D });
+        assert_eq!(scene.connectivity(), Connectivity::Eight);
+        assert_eq!(scene.search_options().connectivity, Connectivity::Eight);
+
+        scene.handle_event(Event::KeyDown { key: Key::D });
+        scene.handle_event(Event::KeyDown { key: Key::D });
+        assert_eq!(scene.connectivity(), Connectivity::Four);
+    }
+
+    #[test]
+    fn draw_shows_the_commands_revealed_so_far() {
+        let mut scene = open_scene(4, 3);
+        scene.set_draw_commands(vec![
+            marker_square(0),
+            marker_square(1),
+            DrawCommand::Clear,
+            marker_square(2),
+        ]);
+
+        let drawn_at = |scene: &mut PathtfindScene, step| {
+            scene.seek_animation(step);
+            let mut cx = RecordingDrawContext::default();
+            scene.draw(&mut cx);
+            rects_of_color(&cx.ops, MARKER)
+        };
+        assert_eq!(drawn_at(&mut scene, 0), vec![]);
+        assert_eq!(
+            drawn_at(&mut scene, 2),
+            vec![scene.get_cell_rect(0, 1), scene.get_cell_rect(1, 1)]
+        );
+        assert_eq!(drawn_at(&mut scene, 4), vec![scene.get_cell_rect(2, 1)]);
+    }
+
+    #[test]
+    fn reset_restores_the_constructed_scene() {
+        let mut scene = PathtfindScene::new(Grid::new(4, 3), (1, 0), (3, 2));
+        scene.set_wall(2, 1, true);
+        scene.set_start((0, 2)).unwrap();
+        scene.set_finish((2, 0)).unwrap();
+        scene.resize_grid(6, 5);
+        scene.run_builtin(Algorithm::Bfs);
+
+        scene.handle_event(Event::KeyDown { key: Key::R });
+
+        assert_eq!(scene.grid(), &Grid::new(4, 3));
+        assert_eq!((scene.start(), scene.finish()), ((1, 0), (3, 2)));
+        assert!(scene.draw_commands.is_empty());
+        assert!(scene.waypoints.is_empty());
+    }
+
+    #[test]
+    fn reset_is_undoable() {
+        let mut scene = open_scene(3, 3);
+        scene.set_wall(1, 1, true);
+        scene.history.commit_stroke();
+        scene.reset();
+
+        scene.undo();
+        assert!(scene.grid().get(1, 1));
+    }
+
+    #[test]
+    fn fast_drag_paints_a_continuous_wall() {
+        let scene = open_scene(6, 3);
+        // A single mouse sample jumps over three cells.
+        let events = left_drag(&scene, &[(1, 1), (5, 1)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+
+        let grid = driver.scene.grid();
+        assert!((1..6).all(|x| grid.get(x, 1)));
+        assert_eq!(grid.count_true(), 5);
+    }
+
+    #[test]
+    fn interpolated_stroke_leaves_the_endpoints_open() {
+        let scene = PathtfindScene::new(Grid::new(5, 3), (2, 1), (4, 2));
+        let events = left_drag(&scene, &[(0, 1), (4, 1)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+
+        let grid = driver.scene.grid();
+        assert!(!grid.get(2, 1));
+        assert!([0, 1, 3, 4].iter().all(|&x| grid.get(x, 1)));
+        assert_eq!(driver.scene.start(), (2, 1));
+    }
+
+    #[test]
+    fn from_maze_str_places_walls_and_endpoints() {
+        let scene = PathtfindScene::from_maze_str("# start: 1,0\n# finish: 1,1\n..XX\nX...\n").unwrap();
+
+        let grid = scene.grid();
+        assert_eq!((grid.width(), grid.height()), (4, 2));
+        // The first row of text is the top of the grid.
+        let walls: Vec<_> = grid.keys().filter(|&(x, y)| grid.get(x, y)).collect();
+        assert_eq!(walls, vec![(0, 0), (2, 1), (3, 1)]);
+        assert_eq!((scene.start(), scene.finish()), ((1, 0), (1, 1)));
+    }
+
+    #[test]
+    fn from_maze_str_defaults_to_opposite_corners() {
+        let scene = PathtfindScene::from_maze_str("...\n...\n").unwrap();
+        assert_eq!((scene.start(), scene.finish()), ((0, 0), (2, 1)));
+    }
+
+    #[test]
+    fn from_maze_str_rejects_bad_endpoints() {
+        let error = |maze| PathtfindScene::from_maze_str(maze).err();
+        assert_eq!(
+            error("# start: 0,1\nX..\n...\n"),
+            Some(ParseMazeError::EndpointOnWall {
+                name: "start",
+                x: 0,
+                y: 1
+            })
+        );
+        assert_eq!(
+            error("# start: 1,1\n# finish: 1,1\n...\n...\n"),
+            Some(ParseMazeError::SameEndpoints)
+        );
+    }
+
+    #[test]
+    fn random_fill_keeps_the_endpoints_open() {
+        let mut scene = open_scene(10, 10);
+        scene.random_fill(1.);
+        assert_eq!(scene.grid().count_true(), 98);
+        assert!(!scene.grid().get(0, 0) && !scene.grid().get(9, 9));
+    }
+
+    #[test]
+    fn random_fill_is_one_undo_step_and_differs_each_time() {
+        let mut scene = open_scene(10, 10);
+        scene.random_fill(0.5);
+        let first = scene.grid().clone();
+        scene.random_fill(0.5);
+        assert_ne!(scene.grid(), &first);
+
+        scene.undo();
+        assert_eq!(scene.grid(), &first);
+        scene.undo();
+        assert_eq!(scene.grid().count_true(), 0);
+    }
+
+    #[test]
+    fn nudge_target_refuses_walls_edges_and_the_other_endpoint() {
+        let mut grid = Grid::new(3, 3);
+        grid.set(1, 0, true);
+        let scene = PathtfindScene::new(grid, (0, 0), (0, 2));
+
+        assert_eq!(scene.nudge_target((0, 0), (0, 1), (0, 2)), Some((0, 1)));
+        assert_eq!(scene.nudge_target((0, 0), (-1, 0), (0, 2)), None);
+        assert_eq!(scene.nudge_target((0, 0), (0, -1), (0, 2)), None);
+        assert_eq!(scene.nudge_target((0, 0), (1, 0), (0, 2)), None);
+        assert_eq!(scene.nudge_target((0, 1), (0, 1), (0, 2)), None);
+        assert_eq!(scene.nudge_target((2, 2), (1, 0), (0, 0)), None);
+    }
+
+    #[test]
+    fn arrow_keys_nudge_the_clicked_endpoint() {
+        let scene = open_scene(4, 4);
+        let events = left_drag(&scene, &[(3, 3)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+        driver.scene.run_builtin(Algorithm::Bfs);
+
+        driver.play(vec![
+            Event::KeyDown { key: Key::Left },
+            Event::KeyDown { key: Key::Down },
+            Event::KeyDown { key: Key::Right },
+        ]);
+        assert_eq!(driver.scene.finish(), (3, 2));
+        assert_eq!(driver.scene.start(), (0, 0));
+        assert!(driver.scene.draw_commands.is_empty());
+
+        // Each nudge is undone on its own.
+        driver.scene.undo();
+        assert_eq!(driver.scene.finish(), (2, 2));
+    }
+
+    #[test]
+    fn arrow_keys_do_nothing_without_a_selection() {
+        let mut scene = open_scene(4, 4);
+        scene.handle_event(Event::KeyDown { key: Key::Up });
+        assert_eq!((scene.start(), scene.finish()), ((0, 0), (3, 3)));
+    }
+
+    #[test]
+    fn middle_click_toggles_waypoints_on_open_cells() {
+        let mut scene = open_scene(4, 4);
+        scene.grid.set(2, 2, true);
+        for &cell in &[(1, 1), (2, 2), (0, 0), (3, 3), (1, 2)] {
+            scene.toggle_waypoint(cell);
+        }
+        assert_eq!(scene.waypoints, vec![(1, 1), (1, 2)]);
+
+        let (x, y) = scene.get_cell_center(1, 1);
+        scene.handle_event(Event::MouseDown {
+            button: MouseButton::Middle,
+            x,
+            y,
+        });
+        assert_eq!(scene.waypoints, vec![(1, 2)]);
+    }
+
+    #[test]
+    fn ordered_multi_goal_search_passes_the_waypoints() {
+        let mut scene = open_scene(4, 1);
+        scene.finish = (1, 0);
+        scene.toggle_waypoint((3, 0));
+        scene.run_multi_goal(GoalMode::Ordered);
+
+        let visited: Vec<_> = scene
+            .draw_commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::AddShape(Shape::Line { to, .. }) => Some(*to),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(visited, vec![(1, 0), (2, 0), (3, 0), (2, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn replay_restarts_the_animation_with_the_same_commands() {
+        let mut scene = open_scene(4, 4);
+        scene.run_builtin(Algorithm::Bfs);
+        let length = scene.animation_length();
+        scene.seek_animation(length);
+        assert_eq!(scene.animation_progress, length as f32);
+
+        scene.handle_event(Event::KeyDown { key: Key::Enter });
+        assert_eq!(scene.animation_length(), length);
+        scene.update(0.);
+        assert_eq!(scene.animation_progress, 0.);
+        assert!(scene.revealed_commands().is_empty());
+    }
+
+    #[test]
+    fn replay_without_an_animation_does_nothing() {
+        let mut scene = open_scene(4, 4);
+        scene.replay();
+        assert_eq!(scene.animation_progress, 0.);
+    }
+
+    #[test]
+    fn invert_walls_flips_cells_but_keeps_the_endpoints_open() {
+        let mut scene = open_scene(3, 2);
+        scene.grid.set(1, 0, true);
+        scene.run_builtin(Algorithm::Bfs);
+        scene.handle_event(Event::KeyDown { key: Key::I });
+
+        let walls: Vec<_> = scene.grid().keys().filter(|&(x, y)| scene.grid().get(x, y)).collect();
+        assert_eq!(walls, vec![(0, 1), (1, 1), (2, 0)]);
+        assert!(scene.draw_commands.is_empty());
+
+        scene.invert_walls();
+        assert_eq!(scene.grid().count_true(), 1);
+        assert!(scene.grid().get(1, 0));
+    }
+
+    #[test]
+    fn every_cell_center_hits_its_own_cell() {
+        let mut scene = open_scene(7, 4);
+        for &bar_width in &[0., DEFAULT_BAR_WIDTH, MAX_BAR_WIDTH] {
+            scene.set_bar_width(bar_width);
+            for (x, y) in scene.grid().keys() {
+                let (center_x, center_y) = scene.get_cell_center(x, y);
+                assert_eq!(scene.get_cell_coordinates(center_x, center_y), (x as i32, y as i32));
+            }
+        }
+    }
+
+    #[test]
+    fn points_near_cell_edges_hit_the_right_cell() {
+        let scene = open_scene(3, 3);
+        let rect = scene.get_cell_rect(1, 2);
+        let inset = 0.01;
+        for &(x, y) in &[
+            (rect.x + inset, rect.y + inset),
+            (rect.x + rect.w - inset, rect.y + rect.h - inset),
+        ] {
+            assert_eq!(scene.get_cell_coordinates(x, y), (1, 2));
+        }
+        assert_eq!(scene.get_cell_coordinates(rect.x - inset, rect.y + inset), (0, 2));
+        assert_eq!(scene.get_cell_coordinates(GRID_MARGIN - inset, GRID_MARGIN - inset), (-1, -1));
+    }
+
+    #[test]
+    fn set_start_and_finish_reject_bad_cells() {
+        let mut scene = open_scene(3, 3);
+        scene.grid.set(1, 1, true);
+
+        assert_eq!(scene.set_start((1, 1)), Err("cell is a wall"));
+        assert_eq!(scene.set_start((3, 0)), Err("cell is outside of the grid"));
+        assert_eq!(scene.set_start((0, 3)), Err("cell is outside of the grid"));
+        assert_eq!(scene.set_start((2, 2)), Err("start and finish must be different cells"));
+        assert_eq!(scene.set_finish((0, 0)), Err("start and finish must be different cells"));
+        assert_eq!(scene.set_finish((1, 1)), Err("cell is a wall"));
+        assert_eq!((scene.start(), scene.finish()), ((0, 0), (2, 2)));
+    }
+
+    #[test]
+    fn set_start_moves_the_start_and_clears_the_path() {
+        let mut scene = open_scene(3, 3);
+        scene.run_builtin(Algorithm::Bfs);
+        assert_eq!(scene.set_start((1, 0)), Ok(()));
+        assert_eq!(scene.set_finish((2, 1)), Ok(()));
+
+        assert_eq!((scene.start(), scene.finish()), ((1, 0), (2, 1)));
+        assert!(scene.draw_commands.is_empty());
+        scene.undo();
+        assert_eq!(scene.finish(), (2, 2));
+    }
+
+    #[test]
+    fn waypoints_stay_off_walls_and_endpoints() {
+        let mut scene = open_scene(4, 4);
+        scene.grid.set(2, 1, true);
+        for &cell in &[(2, 1), (0, 0), (3, 3), (4, 0)] {
+            scene.toggle_waypoint(cell);
+        }
+        assert!(scene.waypoints.is_empty());
+
+        scene.toggle_waypoint((1, 2));
+        scene.toggle_waypoint((1, 2));
+        assert!(scene.waypoints.is_empty());
+    }
+
+    #[test]
+    fn endpoints_cannot_land_on_waypoints() {
+        let mut scene = open_scene(4, 4);
+        scene.toggle_waypoint((1, 0));
+        assert_eq!(scene.set_start((1, 0)), Err("cell is a waypoint"));
+        assert_eq!(scene.set_finish((1, 0)), Err("cell is a waypoint"));
+
+        // Dragging the start onto the waypoint snaps it back on release.
+        let events = left_drag(&scene, &[(0, 0), (1, 0)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+        assert_eq!(driver.scene.start(), (0, 0));
+        assert_eq!(driver.scene.waypoints, vec![(1, 0)]);
+    }
+
+    #[test]
+    fn walls_painted_over_waypoints_remove_them() {
+        let mut scene = open_scene(4, 4);
+        scene.toggle_waypoint((1, 1));
+        let events = left_drag(&scene, &[(1, 1)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+        assert!(driver.scene.grid().get(1, 1));
+        assert!(driver.scene.waypoints.is_empty());
+    }
+
+    #[test]
+    fn later_annotations_replace_earlier_ones_in_the_same_cell() {
+        let annotation = |x, text: &str| {
+            DrawCommand::AddShape(Shape::Annotation {
+                x,
+                y: 1,
+                text: text.to_owned(),
+            })
+        };
+        let mut scene = open_scene(4, 3);
+        scene.set_draw_commands(vec![annotation(1, "3"), annotation(2, "5"), annotation(1, "7")]);
+
+        let texts_at = |scene: &mut PathtfindScene, step| {
+            scene.seek_animation(step);
+            let mut cx = RecordingDrawContext::default();
+            scene.draw(&mut cx);
+            cx.ops
+                .into_iter()
+                .filter_map(|op| match op {
+                    DrawOp::Text { text, .. } if ["3", "5", "7"].contains(&text.as_str()) => Some(text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts_at(&mut scene, 2), vec!["3", "5"]);
+        assert_eq!(texts_at(&mut scene, 3), vec!["5", "7"]);
+    }
+
+    #[test]
+    fn keyboard_actions_cover_the_arrows_space_s_and_f() {
+        assert_eq!(KeyboardAction::from_key(Key::Up), Some(KeyboardAction::MoveCursor(0, 1)));
+        assert_eq!(KeyboardAction::from_key(Key::Down), Some(KeyboardAction::MoveCursor(0, -1)));
+        assert_eq!(KeyboardAction::from_key(Key::Left), Some(KeyboardAction::MoveCursor(-1, 0)));
+        assert_eq!(KeyboardAction::from_key(Key::Right), Some(KeyboardAction::MoveCursor(1, 0)));
+        assert_eq!(KeyboardAction::from_key(Key::Space), Some(KeyboardAction::ToggleWall));
+        assert_eq!(KeyboardAction::from_key(Key::S), Some(KeyboardAction::SetStart));
+        assert_eq!(KeyboardAction::from_key(Key::F), Some(KeyboardAction::SetFinish));
+        assert_eq!(KeyboardAction::from_key(Key::K), None);
+        assert_eq!(KeyboardAction::from_key(Key::Z), None);
+    }
+
+    #[test]
+    fn keyboard_mode_edits_the_cell_under_the_cursor() {
+        let mut scene = open_scene(4, 4);
+        scene.handle_event(Event::KeyDown { key: Key::K });
+        assert!(scene.keyboard_mode());
+        assert_eq!(scene.active_cell(), Some((0, 0)));
+
+        let press = |scene: &mut PathtfindScene, keys: &[Key]| {
+            for &key in keys {
+                scene.handle_event(Event::KeyDown { key });
+            }
+        };
+        // The cursor stops at the edge of the grid.
+        press(&mut scene, &[Key::Left, Key::Down, Key::Right, Key::Up]);
+        assert_eq!(scene.active_cell(), Some((1, 1)));
+
+        press(&mut scene, &[Key::Space]);
+        assert!(scene.grid().get(1, 1));
+        press(&mut scene, &[Key::Space]);
+        assert!(!scene.grid().get(1, 1));
+
+        press(&mut scene, &[Key::Right, Key::S, Key::Up, Key::F]);
+        assert_eq!((scene.start(), scene.finish()), ((2, 1), (2, 2)));
+    }
+
+    #[test]
+    fn keyboard_mode_follows_the_mouse_editing_rules() {
+        let mut scene = open_scene(4, 4);
+        scene.set_keyboard_mode(true);
+        // Endpoints never get walls.
+        scene.handle_event(Event::KeyDown { key: Key::Space });
+        assert!(!scene.grid().get(0, 0));
+
+        // The finish cannot move onto a wall or onto the start.
+        scene.handle_event(Event::KeyDown { key: Key::F });
+        scene.handle_event(Event::KeyDown { key: Key::Right });
+        scene.handle_event(Event::KeyDown { key: Key::Space });
+        scene.handle_event(Event::KeyDown { key: Key::F });
+        assert_eq!(scene.finish(), (3, 3));
+    }
+
+    #[test]
+    fn arrow_keys_move_the_cursor_only_in_keyboard_mode() {
+        let mut scene = open_scene(4, 4);
+        scene.handle_event(Event::KeyDown { key: Key::Space });
+        assert_eq!(scene.grid().count_true(), 0);
+
+        scene.set_keyboard_mode(true);
+        scene.set_keyboard_mode(false);
+        scene.handle_event(Event::KeyDown { key: Key::Right });
+        scene.handle_event(Event::KeyDown { key: Key::Space });
+        assert_eq!(scene.grid().count_true(), 0);
+    }
+
+    #[test]
+    fn flash_counts_down_and_pulses() {
+        let mut flash = Flash {
+            cells: vec![(1, 1)],
+            remaining: 1.,
+            elapsed: 0.,
+        };
+        assert_eq!(flash.alpha(), 0.);
+        assert!(flash.advance(0.25));
+        assert!((flash.remaining - 0.75).abs() < 1e-6);
+        // Half a pulse in, the overlay is at full strength.
+        assert!((flash.alpha() - 1.).abs() < 1e-6);
+        assert!(flash.advance(0.5));
+        assert!(!flash.advance(0.25));
+    }
+
+    #[test]
+    fn flashed_cells_clear_after_the_duration() {
+        let mut scene = open_scene(3, 3);
+        scene.flash_cells(vec![(1, 1), (5, 5)], 0.5);
+        scene.update(0.1);
+        // The out-of-bounds cell is skipped when drawing.
+        let mut cx = RecordingDrawContext::default();
+        scene.draw(&mut cx);
+        assert!(scene.flash.is_some());
+
+        scene.update(0.5);
+        assert!(scene.flash.is_none());
+
+        scene.flash_cells(vec![(1, 1)], 0.);
+        assert!(scene.flash.is_none());
+    }
+
+    #[test]
+    fn a_new_flash_replaces_the_previous_one() {
+        let mut scene = open_scene(3, 3);
+        scene.flash_cells(vec![(0, 1)], 0.3);
+        scene.update(0.2);
+        scene.flash_cells(vec![(1, 1)], 0.3);
+        scene.update(0.2);
+        let flash = scene.flash.as_ref().unwrap();
+        assert_eq!(flash.cells, vec![(1, 1)]);
+        assert!((flash.remaining - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn solvable_indicator_matches_reachability() {
+        for &(maze, solvable) in &[
+            ("...\n.X.\n...\n", true),
+            (".X.\n.X.\n.X.\n", false),
+            ("X..\n.XX\n...\n", false),
+        ] {
+            let mut scene = PathtfindScene::from_maze_str(maze).unwrap();
+            assert_eq!(scene.solvable, None);
+            scene.handle_event(Event::KeyDown { key: Key::J });
+            scene.update(0.);
+            assert_eq!(scene.solvable, Some(solvable), "maze:\n{}", maze);
+        }
+    }
+
+    #[test]
+    fn solvable_indicator_waits_for_edits_to_settle() {
+        let mut scene = open_scene(3, 3);
+        scene.set_show_solvable(true);
+        scene.update(0.);
+        assert_eq!(scene.solvable, Some(true));
+
+        scene.invert_walls();
+        scene.update(SOLVABLE_CHECK_DELAY / 2.);
+        assert_eq!(scene.solvable, Some(true));
+        scene.update(SOLVABLE_CHECK_DELAY);
+        assert_eq!(scene.solvable, Some(false));
+
+        // With diagonal moves the start reaches the finish through the center.
+        scene.grid.set(1, 1, false);
+        scene.set_connectivity(Connectivity::Eight);
+        scene.update(SOLVABLE_CHECK_DELAY * 2.);
+        assert_eq!(scene.solvable, Some(true));
+    }
+
+    #[test]
+    fn consecutive_clears_draw_every_step_without_panicking() {
+        use DrawCommand::Clear;
+
+        let mut scene = open_scene(4, 3);
+        scene.set_draw_commands(vec![
+            Clear,
+            Clear,
+            marker_square(0),
+            Clear,
+            Clear,
+            marker_square(1),
+            marker_square(2),
+            Clear,
+        ]);
+
+        let mut drawn = vec![];
+        for step in 0..=scene.draw_commands.len() {
+            scene.seek_animation(step);
+            let mut cx = RecordingDrawContext::default();
+            scene.draw(&mut cx);
+            drawn.push(rects_of_color(&cx.ops, MARKER).len());
+        }
+        assert_eq!(drawn, vec![0, 0, 0, 1, 0, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn locked_scene_ignores_edits_but_still_searches() {
+        let scene = open_scene(4, 3);
+        let mut events = vec![Event::KeyDown { key: Key::L }];
+        events.extend(left_drag(&scene, &[(1, 0), (1, 1), (1, 2)]));
+        events.extend(left_drag(&scene, &[(0, 0), (0, 1)]));
+        let (x, y) = scene.get_cell_center(2, 1);
+        events.push(Event::MouseDown {
+            button: MouseButton::Middle,
+            x,
+            y,
+        });
+        events.push(Event::MouseMoved { x, y });
+        events.extend(vec![Event::KeyDown { key: Key::K }, Event::KeyDown { key: Key::Space }]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+
+        let scene = &mut driver.scene;
+        assert!(scene.locked());
+        assert_eq!(scene.grid().count_true(), 0);
+        assert_eq!((scene.start(), scene.finish()), ((0, 0), (3, 2)));
+        assert!(scene.waypoints().is_empty());
+        // Hovering still moves the highlight.
+        assert_eq!(scene.active_cell(), Some((2, 1)));
+
+        scene.run_builtin(Algorithm::Bfs);
+        assert!(!scene.draw_commands.is_empty());
+    }
+
+    #[test]
+    fn unlocking_allows_edits_again() {
+        let mut scene = open_scene(4, 3);
+        scene.set_locked(true);
+        scene.set_locked(false);
+        let events = left_drag(&scene, &[(1, 0), (1, 1)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+        assert!(driver.scene.grid().get(1, 0) && driver.scene.grid().get(1, 1));
+    }
+
+    #[test]
+    fn transform_maze_moves_endpoints_walls_and_waypoints() {
+        let scene = open_scene(3, 2);
+        let events = left_drag(&scene, &[(1, 0)]);
+        let mut driver = SceneDriver::new(scene);
+        driver.play(events);
+        driver.scene.toggle_waypoint((2, 0));
+
+        driver.play(vec![Event::KeyDown { key: Key::Comma }]);
+        let scene = &mut driver.scene;
+        assert_eq!((scene.grid().width(), scene.grid().height()), (2, 3));
+        assert_eq!((scene.start(), scene.finish()), ((1, 0), (0, 2)));
+        assert_eq!(scene.waypoints(), &[(1, 2)]);
+        assert!(scene.grid().get(1, 1));
+        assert_eq!(scene.grid().count_true(), 1);
+
+        // The stroke was recorded for the old shape, so it can't be undone.
+        scene.undo();
+        assert!(scene.grid().get(1, 1));
+    }
+
+    #[test]
+    fn flips_and_transpose_keep_the_endpoints_on_their_cells() {
+        let mut scene = open_scene(3, 2);
+        scene.transform_maze(GridTransform::FlipHorizontal);
+        assert_eq!((scene.start(), scene.finish()), ((2, 0), (0, 1)));
+        scene.transform_maze(GridTransform::FlipVertical);
+        assert_eq!((scene.start(), scene.finish()), ((2, 1), (0, 0)));
+        scene.transform_maze(GridTransform::Transpose);
+        assert_eq!((scene.start(), scene.finish()), ((1, 2), (0, 0)));
+        assert_eq!((scene.grid().width(), scene.grid().height()), (2, 3));
+    }
+
+    #[test]
+    fn final_path_only_mode_draws_nothing_before_the_last_clear() {
+        let mut scene = open_scene(4, 3);
+        scene.handle_event(Event::KeyDown { key: Key::O });
+        scene.set_draw_commands(vec![
+            marker_square(0),
+            DrawCommand::Clear,
+            marker_square(1),
+            DrawCommand::Clear,
+            marker_square(2),
+        ]);
+        scene.update(0.);
+        assert_eq!(scene.animation_progress, 4.);
+
+        let drawn = |scene: &mut PathtfindScene| {
+            let mut cx = RecordingDrawContext::default();
+            scene.draw(&mut cx);
+            rects_of_color(&cx.ops, MARKER)
+        };
+        for step in 0..=4 {
+            scene.seek_animation(step);
+            assert_eq!(drawn(&mut scene), vec![], "step {}", step);
+        }
+        scene.seek_animation(5);
+        assert_eq!(drawn(&mut scene), vec![scene.get_cell_rect(2, 1)]);
+    }
+
+    #[test]
+    fn turning_on_final_path_only_skips_the_rest_of_the_exploration() {
+        let mut scene = open_scene(4, 3);
+        scene.run_builtin(Algorithm::Bfs);
+        scene.update(0.);
+        let last_clear = scene
+            .draw_commands
+            .iter()
+            .rposition(|cmd| matches!(cmd, DrawCommand::Clear))
+            .unwrap();
+
+        scene.set_final_path_only(true);
+        assert_eq!(scene.animation_progress, (last_clear + 1) as f32);
+        // Turning it off again doesn't rewind.
+        scene.set_final_path_only(false);
+        assert_eq!(scene.animation_progress, (last_clear + 1) as f32);
+    }
+
+    #[test]
+    fn closest_approach_is_announced_until_the_path_changes() {
+        let mut scene = PathtfindScene::from_maze_str("..X.\n..X.\n..X.\n").unwrap();
+        scene.run_builtin(Algorithm::Bfs);
+        assert!(!scene.showing_closest_approach);
+
+        scene.handle_event(Event::KeyDown { key: Key::A });
+        scene.run_builtin(Algorithm::Bfs);
+        assert!(scene.showing_closest_approach);
+        let mut cx = RecordingDrawContext::default();
+        scene.draw(&mut cx);
+        assert!(cx.ops.iter().any(|op| matches!(
+            op,
+            DrawOp::Text { text, .. } if text == "Goal unreachable; showing closest approach"
+        )));
+
+        scene.set_draw_commands(vec![]);
+        assert!(!scene.showing_closest_approach);
+    }
+
+    #[test]
+    fn search_options_carry_the_chosen_convention() {
+        let mut scene = open_scene(3, 2);
+        assert_eq!(scene.search_options().convention, CoordConvention::RowCol);
+        scene.set_convention(CoordConvention::XY);
+        assert_eq!(scene.convention(), CoordConvention::XY);
+        assert_eq!(scene.search_options().convention, CoordConvention::XY);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::grid::{FromBytesError, Grid};
+use crate::scene::MAX_GRID_SIZE;
+
+use std::convert::TryInto;
+use std::fmt;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Bumped whenever the layout below changes. Links made with older versions
+// keep decoding as long as their version stays supported in `decode`.
+pub const SHARE_FORMAT_VERSION: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShareError {
+    Truncated,
+    UnsupportedVersion(u8),
+    Grid(FromBytesError),
+    GridTooLarge { width: usize, height: usize },
+    InvalidEndpoints,
+    InvalidCode,
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShareError::Truncated => write!(f, "shared scene is truncated"),
+            ShareError::UnsupportedVersion(version) => {
+                write!(f, "shared scene has unsupported format version {}", version)
+            }
+            ShareError::Grid(err) => write!(f, "shared scene has a broken grid: {}", err),
+            ShareError::GridTooLarge { width, height } => write!(
+                f,
+                "shared grid is {}x{}, at most {}x{} is supported",
+                width, height, MAX_GRID_SIZE, MAX_GRID_SIZE
+            ),
+            ShareError::InvalidEndpoints => write!(f, "shared start and finish are not valid"),
+            ShareError::InvalidCode => write!(f, "shared code is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Everything a link needs to reproduce a puzzle. Encoded as:
+//
+//     byte 0       format version
+//     bytes 1..17  start x, start y, finish x, finish y, u32 little endian
+//     bytes 17..21 length of the grid bytes, u32 little endian
+//     then         the grid in the `Grid::to_bytes` layout
+//     then         the code as UTF-8, up to the end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedScene {
+    pub grid: Grid<bool>,
+    pub start: (usize, usize),
+    pub finish: (usize, usize),
+    pub code: String,
+}
+
+impl SharedScene {
+    pub fn encode(&self) -> Vec<u8> {
+        let grid = self.grid.to_bytes();
+        let mut bytes = vec![SHARE_FORMAT_VERSION];
+        for &value in &[self.start.0, self.start.1, self.finish.0, self.finish.1, grid.len()] {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&grid);
+        bytes.extend_from_slice(self.code.as_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ShareError> {
+        let (&version, mut rest) = bytes.split_first().ok_or(ShareError::Truncated)?;
+        if version != SHARE_FORMAT_VERSION {
+            return Err(ShareError::UnsupportedVersion(version));
+        }
+
+        let mut read_u32 = || -> Result<usize, ShareError> {
+            if rest.len() < 4 {
+                return Err(ShareError::Truncated);
+            }
+            let (word, tail) = rest.split_at(4);
+            rest = tail;
+            Ok(u32::from_le_bytes(word.try_into().unwrap()) as usize)
+        };
+        let start = (read_u32()?, read_u32()?);
+        let finish = (read_u32()?, read_u32()?);
+        let grid_length = read_u32()?;
+        if rest.len() < grid_length {
+            return Err(ShareError::Truncated);
+        }
+        let (grid, code) = rest.split_at(grid_length);
+
+        let grid = Grid::from_bytes(grid).map_err(ShareError::Grid)?;
+        if grid.width() > MAX_GRID_SIZE || grid.height() > MAX_GRID_SIZE {
+            return Err(ShareError::GridTooLarge {
+                width: grid.width(),
+                height: grid.height(),
+            });
+        }
+        let is_open = |(x, y): (usize, usize)| grid.get_checked(x, y) == Ok(false);
+        if !is_open(start) || !is_open(finish) || start == finish {
+            return Err(ShareError::InvalidEndpoints);
+        }
+        let code = String::from_utf8(code.to_vec()).map_err(|_| ShareError::InvalidCode)?;
+
+        Ok(Self {
+            grid,
+            start,
+            finish,
+            code,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared(start: (usize, usize), finish: (usize, usize)) -> SharedScene {
+        let mut grid = Grid::new(3, 2);
+        grid.set(1, 0, true);
+        SharedScene {
+            grid,
+            start,
+            finish,
+            code: "def find_path(grid, start, finish):\n    return []\n".to_owned(),
+        }
+    }
+
+    #[test]
+    fn decode_reverses_encode() {
+        let scene = shared((0, 0), (2, 1));
+        assert_eq!(SharedScene::decode(&scene.encode()), Ok(scene));
+    }
+
+    // Links are crafted by anyone, and the scene they are loaded into must
+    // never see endpoints it would refuse itself.
+    #[test]
+    fn decode_rejects_bad_endpoints() {
+        for &(start, finish) in &[((2, 1), (2, 1)), ((1, 0), (2, 1)), ((0, 0), (0, 2))] {
+            let bytes = shared(start, finish).encode();
+            assert_eq!(SharedScene::decode(&bytes), Err(ShareError::InvalidEndpoints));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_links() {
+        let bytes = shared((0, 0), (2, 1)).encode();
+        assert_eq!(SharedScene::decode(&bytes[..10]), Err(ShareError::Truncated));
+        assert_eq!(SharedScene::decode(&[]), Err(ShareError::Truncated));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::scene::{colors, Color};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// How wall cells are filled. Solid is a plain square, Bordered adds a darker
+// frame, Hatched draws diagonal stripes over the floor instead of filling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WallStyle {
+    Solid,
+    Bordered,
+    Hatched,
+}
+
+impl Default for WallStyle {
+    fn default() -> Self {
+        WallStyle::Solid
+    }
+}
+
+impl WallStyle {
+    pub fn next(self) -> Self {
+        match self {
+            WallStyle::Solid => WallStyle::Bordered,
+            WallStyle::Bordered => WallStyle::Hatched,
+            WallStyle::Hatched => WallStyle::Solid,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Color,
+    pub bars: Color,
+    pub wall: Color,
+    pub wall_style: WallStyle,
+    pub start: Color,
+    pub finish: Color,
+    pub path: Color,
+    // Draw the start and finish as rings so the cell under them shows.
+    pub outlined_markers: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        CLASSIC
+    }
+}
+
+pub const CLASSIC: Theme = Theme {
+    name: "Classic",
+    background: colors::LIGHTGRAY,
+    bars: colors::WHITE,
+    wall: colors::GRAY,
+    wall_style: WallStyle::Solid,
+    start: colors::DARKGREEN,
+    finish: colors::DARKBLUE,
+    path: colors::LIME,
+    outlined_markers: false,
+};
+
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "High contrast",
+    background: colors::WHITE,
+    bars: colors::LIGHTGRAY,
+    wall: colors::BLACK,
+    wall_style: WallStyle::Solid,
+    start: colors::BLUE,
+    finish: colors::RED,
+    path: colors::ORANGE,
+    outlined_markers: false,
+};
+
+// Blue/orange is distinguishable with the common forms of color blindness.
+pub const COLORBLIND: Theme = Theme {
+    name: "Colorblind friendly",
+    background: colors::LIGHTGRAY,
+    bars: colors::WHITE,
+    wall: colors::DARKGRAY,
+    wall_style: WallStyle::Solid,
+    start: colors::BLUE,
+    finish: colors::ORANGE,
+    path: colors::YELLOW,
+    outlined_markers: false,
+};
+
+pub const DARK: Theme = Theme {
+    name: "Dark",
+    background: colors::DARKGRAY,
+    bars: colors::BLACK,
+    wall: colors::LIGHTGRAY,
+    wall_style: WallStyle::Solid,
+    start: colors::GREEN,
+    finish: colors::SKYBLUE,
+    path: colors::GOLD,
+    outlined_markers: false,
+};
+
+pub const PRESETS: [Theme; 4] = [CLASSIC, HIGH_CONTRAST, COLORBLIND, DARK];
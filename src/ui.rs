@@ -1,10 +1,25 @@
+use crate::examples::{LoadedCode, EXAMPLES};
+use crate::grid::{CoordConvention, Grid};
+use crate::runner::Image;
 use crate::scene::PathtfindScene;
-use crate::pathfind::find_and_render_path;
+use crate::share::SharedScene;
+use crate::pathfind::{
+    cache::SearchCache, compare_with_optimal, traceback_line, SearchOptions, DEFAULT_ENTRY_POINT,
+};
+use crate::theme;
 
 use wasm_bindgen::JsCast;
 
+use std::convert::TryFrom;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+
+const SLOT_STORAGE_PREFIX: &str = "pathfind_demo.slot.";
+// The default comes first, so it is the one selected when the page loads.
+const CONVENTIONS: [(&str, CoordConvention); 2] = [
+    ("grid[row][col]", CoordConvention::RowCol),
+    ("grid[x][y]", CoordConvention::XY),
+];
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -12,9 +27,45 @@ pub fn init(scene: Rc<RefCell<PathtfindScene>>) {
     let text_code = get_html_element("text-code");
     let text_output = get_html_element("text-output");
     let button_run = get_html_element("button-run");
+    let button_compare = get_html_element("button-compare");
+    let button_share = get_html_element("button-share");
+    let select_theme = get_html_element("select-theme");
+    let select_convention = get_html_element("select-convention");
+    let select_slot = get_html_element("select-slot");
+    let input_entry_point = get_html_element("input-entry-point");
+    let select_example = get_html_element("select-example");
+    let text_cursor = get_html_element("text-cursor");
+    let input_background = get_html_element("input-background");
+    let button_background = get_html_element("button-background");
 
-    let ui_manager = Box::leak(Box::new(UiManager { scene, text_code, text_output, button_run }));
+    let ui_manager = Box::leak(Box::new(UiManager {
+        scene,
+        text_code,
+        text_output,
+        button_run,
+        button_compare,
+        button_share,
+        select_theme,
+        select_convention,
+        select_slot,
+        input_entry_point,
+        select_example,
+        text_cursor,
+        input_background,
+        button_background,
+        search_cache: RefCell::new(SearchCache::default()),
+        slots: RefCell::new(load_slots()),
+        current_slot: Cell::new(0),
+        loaded_code: RefCell::new(LoadedCode::new()),
+    }));
+    ui_manager.init_theme_options();
+    ui_manager.init_convention_options();
+    ui_manager.init_slot_options();
+    ui_manager.load_shared_scene();
+    ui_manager.init_example_options();
     ui_manager.init_callbacks();
+    ui_manager.init_live_searcher();
+    ui_manager.init_cursor_readout();
 }
 
 fn get_html_element<T: JsCast + Clone>(name: &str) -> T {
@@ -30,6 +81,87 @@ fn get_html_element<T: JsCast + Clone>(name: &str) -> T {
         .clone()
 }
 
+// Longer hashes are refused before decoding, whatever they contain.
+const MAX_SHARE_HASH_LENGTH: usize = 1 << 20;
+
+fn decode_share_hash(hash: &str) -> Result<SharedScene, String> {
+    if hash.len() > MAX_SHARE_HASH_LENGTH {
+        return Err("the link is too long".to_owned());
+    }
+    let window = web_sys::window().ok_or_else(|| "no window".to_owned())?;
+    let binary = window
+        .atob(hash)
+        .map_err(|_| "the link is not valid base64".to_owned())?;
+    let bytes = binary
+        .chars()
+        .map(|ch| u8::try_from(u32::from(ch)).map_err(|_| "the link is not valid base64".to_owned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    SharedScene::decode(&bytes).map_err(|err| err.to_string())
+}
+
+// Draws the image onto an offscreen canvas to get at its RGBA pixels.
+fn read_image_pixels(image: &web_sys::HtmlImageElement) -> Option<Image> {
+    let (width, height) = (image.natural_width(), image.natural_height());
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return None;
+    }
+    let canvas = web_sys::window()?
+        .document()?
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .ok()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = canvas
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .ok()?;
+    context.draw_image_with_html_image_element(image, 0., 0.).ok()?;
+    let data = context
+        .get_image_data(0., 0., width as f64, height as f64)
+        .ok()?;
+    Some(Image {
+        bytes: data.data().0,
+        width: width as u16,
+        height: height as u16,
+    })
+}
+
+fn request_animation_frame(callback: &wasm_bindgen::closure::Closure<dyn FnMut()>) {
+    web_sys::window()
+        .expect("global window does not exists")
+        .request_animation_frame(callback.as_ref().unchecked_ref())
+        .expect("failed to request an animation frame");
+}
+
+fn confirm(message: &str) -> bool {
+    web_sys::window()
+        .and_then(|window| window.confirm_with_message(message).ok())
+        .unwrap_or(false)
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+fn slot_storage_key(slot: usize) -> String {
+    format!("{}{}", SLOT_STORAGE_PREFIX, EXAMPLES[slot].name)
+}
+
+fn load_slots() -> Vec<String> {
+    let storage = local_storage();
+    (0..EXAMPLES.len())
+        .map(|slot| {
+            storage
+                .as_ref()
+                .and_then(|storage| storage.get_item(&slot_storage_key(slot)).ok().flatten())
+                .unwrap_or_else(|| EXAMPLES[slot].code.to_owned())
+        })
+        .collect()
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 struct UiManager {
@@ -37,31 +169,412 @@ struct UiManager {
     text_code: web_sys::HtmlTextAreaElement,
     text_output: web_sys::HtmlTextAreaElement,
     button_run: web_sys::HtmlButtonElement,
+    button_compare: web_sys::HtmlButtonElement,
+    button_share: web_sys::HtmlButtonElement,
+    select_theme: web_sys::HtmlSelectElement,
+    select_convention: web_sys::HtmlSelectElement,
+    select_slot: web_sys::HtmlSelectElement,
+    input_entry_point: web_sys::HtmlInputElement,
+    select_example: web_sys::HtmlSelectElement,
+    text_cursor: web_sys::HtmlElement,
+    input_background: web_sys::HtmlInputElement,
+    button_background: web_sys::HtmlButtonElement,
+    search_cache: RefCell<SearchCache>,
+    slots: RefCell<Vec<String>>,
+    current_slot: Cell<usize>,
+    // Starts out as the examples, so code kept in the browser from an earlier
+    // visit counts as work to keep.
+    loaded_code: RefCell<LoadedCode>,
 }
 
 impl UiManager {
+    fn init_theme_options(&self) {
+        for (i, preset) in theme::PRESETS.iter().enumerate() {
+            let option = web_sys::HtmlOptionElement::new_with_text_and_value(preset.name, &i.to_string())
+                .expect("failed to create theme option");
+            self.select_theme
+                .append_child(&option)
+                .expect("failed to add theme option");
+        }
+    }
+
+    fn init_convention_options(&self) {
+        for (i, (name, _)) in CONVENTIONS.iter().enumerate() {
+            let option = web_sys::HtmlOptionElement::new_with_text_and_value(name, &i.to_string())
+                .expect("failed to create convention option");
+            self.select_convention
+                .append_child(&option)
+                .expect("failed to add convention option");
+        }
+    }
+
+    fn init_slot_options(&self) {
+        for (i, example) in EXAMPLES.iter().enumerate() {
+            let option = web_sys::HtmlOptionElement::new_with_text_and_value(example.name, &i.to_string())
+                .expect("failed to create slot option");
+            self.select_slot
+                .append_child(&option)
+                .expect("failed to add slot option");
+        }
+        self.text_code.set_value(&self.slots.borrow()[0]);
+    }
+
+    // The first option is a placeholder, so picking any example is a change.
+    fn init_example_options(&self) {
+        let placeholder = web_sys::HtmlOptionElement::new_with_text_and_value("Load example...", "")
+            .expect("failed to create example option");
+        self.select_example
+            .append_child(&placeholder)
+            .expect("failed to add example option");
+        for (i, example) in EXAMPLES.iter().enumerate() {
+            let option = web_sys::HtmlOptionElement::new_with_text_and_value(example.name, &i.to_string())
+                .expect("failed to create example option");
+            self.select_example
+                .append_child(&option)
+                .expect("failed to add example option");
+        }
+    }
+
     fn init_callbacks(&'static self) {
         let on_run_clicked = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
             self.on_run_clicked()
         }) as Box<dyn Fn()>);
         self.button_run.set_onclick(Some(on_run_clicked.as_ref().unchecked_ref()));
         on_run_clicked.forget();
+
+        let on_compare_clicked = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_compare_clicked()
+        }) as Box<dyn Fn()>);
+        self.button_compare.set_onclick(Some(on_compare_clicked.as_ref().unchecked_ref()));
+        on_compare_clicked.forget();
+
+        let on_background_clicked = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_background_clicked()
+        }) as Box<dyn Fn()>);
+        self.button_background.set_onclick(Some(on_background_clicked.as_ref().unchecked_ref()));
+        on_background_clicked.forget();
+
+        let on_share_clicked = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_share_clicked()
+        }) as Box<dyn Fn()>);
+        self.button_share.set_onclick(Some(on_share_clicked.as_ref().unchecked_ref()));
+        on_share_clicked.forget();
+
+        let on_theme_changed = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_theme_changed()
+        }) as Box<dyn Fn()>);
+        self.select_theme.set_onchange(Some(on_theme_changed.as_ref().unchecked_ref()));
+        on_theme_changed.forget();
+
+        let on_convention_changed = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_convention_changed()
+        }) as Box<dyn Fn()>);
+        self.select_convention.set_onchange(Some(on_convention_changed.as_ref().unchecked_ref()));
+        on_convention_changed.forget();
+
+        let on_slot_changed = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_slot_changed()
+        }) as Box<dyn Fn()>);
+        self.select_slot.set_onchange(Some(on_slot_changed.as_ref().unchecked_ref()));
+        on_slot_changed.forget();
+
+        let on_example_selected = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.on_example_selected()
+        }) as Box<dyn Fn()>);
+        self.select_example.set_onchange(Some(on_example_selected.as_ref().unchecked_ref()));
+        on_example_selected.forget();
+
+        let on_code_input = wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.save_current_slot()
+        }) as Box<dyn Fn()>);
+        self.text_code.set_oninput(Some(on_code_input.as_ref().unchecked_ref()));
+        on_code_input.forget();
+    }
+
+    // Live mode runs the code currently in the editor. The scene is already
+    // borrowed when the searcher is called, so it only gets the search inputs.
+    fn init_live_searcher(&'static self) {
+        let searcher = move |grid: &Grid<bool>,
+                             start: (usize, usize),
+                             finish: (usize, usize),
+                             options: &SearchOptions| {
+            self.text_output.set_value("");
+            let options = SearchOptions {
+                entry_point: self.entry_point(),
+                ..options.clone()
+            };
+            let maybe_search = self.search_cache.borrow_mut().find_and_render_path(
+                &self.text_code.value(),
+                grid,
+                start,
+                finish,
+                &options,
+            );
+            match maybe_search {
+                Ok(search) => {
+                    self.text_output.set_value(&search.summary());
+                    Some(search.draw_commands)
+                }
+                Err(traceback) => {
+                    self.show_error(&traceback);
+                    None
+                }
+            }
+        };
+        self.scene.borrow_mut().set_live_searcher(Box::new(searcher));
+    }
+
+    fn save_current_slot(&self) {
+        let slot = self.current_slot.get();
+        let code = self.text_code.value();
+        if let Some(storage) = local_storage() {
+            let _ = storage.set_item(&slot_storage_key(slot), &code);
+        }
+        self.slots.borrow_mut()[slot] = code;
+    }
+
+    fn on_slot_changed(&self) {
+        let index = self.select_slot.selected_index();
+        if index < 0 || index as usize >= EXAMPLES.len() {
+            return;
+        }
+        self.save_current_slot();
+        self.current_slot.set(index as usize);
+        self.text_code.set_value(&self.slots.borrow()[index as usize]);
+    }
+
+    // Asks before code that differs from what was last loaded into the slot
+    // is replaced, see `LoadedCode`.
+    fn confirm_replace_code(&self, message: &str) -> bool {
+        let code = self.text_code.value();
+        !self.loaded_code.borrow().would_lose_edits(self.current_slot.get(), &code) || confirm(message)
+    }
+
+    fn load_code(&self, code: &str) {
+        self.text_code.set_value(code);
+        self.loaded_code.borrow_mut().load(self.current_slot.get(), code);
+    }
+
+    // Runs before the runner starts, so the scene can simply be replaced. A link
+    // that can't be decoded leaves the default scene and says why.
+    fn load_shared_scene(&self) {
+        let hash = match web_sys::window().and_then(|window| window.location().hash().ok()) {
+            Some(hash) if hash.len() > 1 => hash,
+            _ => return,
+        };
+        let loaded = decode_share_hash(&hash[1..]).and_then(|shared| {
+            let scene = PathtfindScene::try_new(shared.grid, shared.start, shared.finish)
+                .map_err(|err| err.to_owned())?;
+            Ok((scene, shared.code))
+        });
+        match loaded {
+            Ok((scene, code)) => {
+                *self.scene.borrow_mut() = scene;
+                if self.confirm_replace_code("Replace the code in the editor with the code from the link?") {
+                    self.load_code(&code);
+                }
+            }
+            Err(err) => self.text_output.set_value(&format!("Ignoring the shared link: {}", err)),
+        }
+    }
+
+    fn on_share_clicked(&self) {
+        let shared = {
+            let scene = self.scene.borrow();
+            SharedScene {
+                grid: scene.grid().clone(),
+                start: scene.start(),
+                finish: scene.finish(),
+                code: self.text_code.value(),
+            }
+        };
+        let window = web_sys::window().expect("global window does not exists");
+        let binary: String = shared.encode().into_iter().map(char::from).collect();
+        let hash = match window.btoa(&binary) {
+            Ok(hash) if hash.len() <= MAX_SHARE_HASH_LENGTH => hash,
+            _ => {
+                self.text_output.set_value("The scene is too large to share as a link");
+                return;
+            }
+        };
+        let location = window.location();
+        if location.set_hash(&hash).is_err() {
+            self.text_output.set_value("Failed to update the page address");
+            return;
+        }
+        let href = location.href().unwrap_or_default();
+        self.text_output.set_value(&format!("Link to this scene:\n{}", href));
+    }
+
+    // Replaces the editor contents with an example, asking first if that
+    // would throw away edits.
+    fn on_example_selected(&self) {
+        let index = self.select_example.selected_index() - 1;
+        self.select_example.set_selected_index(0);
+        let example = match EXAMPLES.get(index as usize) {
+            Some(example) if index >= 0 => example,
+            _ => return,
+        };
+
+        if !self.confirm_replace_code("Replace the code in the editor with the example?") {
+            return;
+        }
+        self.load_code(example.code);
+        self.save_current_slot();
+    }
+
+    // An empty URL removes the background. Images from other origins only work
+    // if they are served with CORS headers, otherwise their pixels can't be read.
+    fn on_background_clicked(&'static self) {
+        let url = self.input_background.value();
+        if url.trim().is_empty() {
+            self.scene.borrow_mut().clear_background_image();
+            return;
+        }
+
+        let image = web_sys::HtmlImageElement::new().expect("failed to create an image element");
+        image.set_cross_origin(Some("anonymous"));
+        let loaded = image.clone();
+        let on_load = wasm_bindgen::closure::Closure::once_into_js(move || {
+            match read_image_pixels(&loaded) {
+                Some(pixels) => self.scene.borrow_mut().set_background_image(pixels),
+                None => self.text_output.set_value("Failed to read the background image"),
+            }
+        });
+        image.set_onload(Some(on_load.unchecked_ref()));
+        image.set_src(&url);
+        let on_error = wasm_bindgen::closure::Closure::once_into_js(move || {
+            self.text_output.set_value(&format!("Failed to load background image {}", url))
+        });
+        image.set_onerror(Some(on_error.unchecked_ref()));
+    }
+
+    fn on_theme_changed(&self) {
+        let index = self.select_theme.selected_index();
+        if let Some(&preset) = theme::PRESETS.get(index as usize) {
+            self.scene.borrow_mut().set_theme(preset);
+        }
+    }
+
+    fn on_convention_changed(&self) {
+        let index = self.select_convention.selected_index();
+        if let Some(&(_, convention)) = CONVENTIONS.get(index as usize) {
+            self.scene.borrow_mut().set_convention(convention);
+        }
+    }
+
+    // Searches run on the page's only thread, so nothing could cancel them
+    // while they run and they get no `CancelToken`.
+    fn search_options(&self, scene: &PathtfindScene) -> SearchOptions {
+        SearchOptions {
+            entry_point: self.entry_point(),
+            ..scene.search_options()
+        }
+    }
+
+    fn entry_point(&self) -> String {
+        let name = self.input_entry_point.value();
+        let name = name.trim();
+        if name.is_empty() {
+            DEFAULT_ENTRY_POINT.to_owned()
+        } else {
+            name.to_owned()
+        }
+    }
+
+    // Polls the scene once per animation frame, after macroquad has handled
+    // the mouse events of the frame.
+    fn init_cursor_readout(&'static self) {
+        let callback: Rc<RefCell<Option<wasm_bindgen::closure::Closure<dyn FnMut()>>>> =
+            Rc::new(RefCell::new(None));
+        let next_frame = callback.clone();
+        *callback.borrow_mut() = Some(wasm_bindgen::closure::Closure::wrap(Box::new(move || {
+            self.update_cursor_readout();
+            request_animation_frame(next_frame.borrow().as_ref().unwrap());
+        }) as Box<dyn FnMut()>));
+        request_animation_frame(callback.borrow().as_ref().unwrap());
+    }
+
+    fn update_cursor_readout(&self) {
+        let scene = match self.scene.try_borrow() {
+            Ok(scene) => scene,
+            Err(_) => return,
+        };
+        let text = match scene.active_cell() {
+            Some((x, y)) => format!("Cell: ({}, {})", x, y),
+            None => "Cell: -".to_owned(),
+        };
+        if self.text_cursor.text_content().as_deref() != Some(text.as_str()) {
+            self.text_cursor.set_text_content(Some(&text));
+        }
     }
 
     fn on_run_clicked(&self) {
         self.text_output.set_value("");
         let mut scene = self.scene.borrow_mut();
 
-        let maybe_draw_commands = find_and_render_path(
+        let maybe_search = self.search_cache.borrow_mut().find_and_render_path(
+            &self.text_code.value(),
+            scene.grid(),
+            scene.start(),
+            scene.finish(),
+            &self.search_options(&scene),
+        );
+
+        match maybe_search {
+            Ok(search) => {
+                self.text_output.set_value(&search.summary());
+                scene.set_draw_commands(search.draw_commands);
+            }
+            Err(traceback) => self.show_error(&traceback),
+        }
+    }
+
+    fn on_compare_clicked(&self) {
+        self.text_output.set_value("");
+        let mut scene = self.scene.borrow_mut();
+
+        let maybe_comparison = compare_with_optimal(
             &self.text_code.value(),
             scene.grid(),
             scene.start(),
             scene.finish(),
+            &self.search_options(&scene),
         );
 
-        match maybe_draw_commands {
-            Ok(draw_commands) => scene.set_draw_commands(draw_commands),
-            Err(traceback) => self.text_output.set_value(&traceback),
+        match maybe_comparison {
+            Ok(comparison) => {
+                self.text_output.set_value(&comparison.summary());
+                scene.set_draw_commands(comparison.draw_commands);
+            }
+            Err(traceback) => self.show_error(&traceback),
+        }
+    }
+
+    fn show_error(&self, traceback: &str) {
+        let line = match traceback_line(traceback) {
+            Some(line) => line,
+            None => {
+                self.text_output.set_value(traceback);
+                return;
+            }
+        };
+        self.text_output.set_value(&format!("{}\nError on line {}", traceback.trim_end(), line));
+
+        // Selection offsets are in UTF-16 code units, as everywhere in JS.
+        let code = self.text_code.value();
+        let line_count = code.lines().count().max(1);
+        let mut offset = 0;
+        for (i, text) in code.split('\n').enumerate() {
+            let length = text.encode_utf16().count() as u32;
+            if i + 1 == line {
+                let _ = self.text_code.focus();
+                let _ = self.text_code.set_selection_range(offset, offset + length);
+                let line_height = self.text_code.scroll_height() / line_count as i32;
+                self.text_code.set_scroll_top(line_height * (i as i32 - 3).max(0));
+                break;
+            }
+            offset += length + 1;
         }
     }
 }